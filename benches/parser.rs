@@ -0,0 +1,69 @@
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use rslox::{Lexer, Parser};
+
+/// A program with a mix of declarations, control flow and expressions.
+fn program(functions: usize) -> String {
+    (0..functions)
+        .map(|i| {
+            format!(
+                "fun f{i}(a, b) {{
+    var sum = 0;
+    for (var j = 0; j < a; j = j + 1) {{
+        if (j == b or j > 10 and !(j < 3)) sum = sum + j * 2;
+        else sum = sum - 1;
+    }}
+    return \"result ${{sum}}: \" + f{i}(a - 1, b).field;
+}}
+"
+            )
+        })
+        .collect()
+}
+
+fn bench(c: &mut Criterion) {
+    let source = program(500);
+
+    let mut group = c.benchmark_group("frontend");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("tokenize", |b| {
+        b.iter(|| Lexer::new(black_box(&source)).count())
+    });
+    // Generated code often comes as a single line.
+    let one_line = source.replace('\n', " ");
+    group.bench_function("tokenize one line", |b| {
+        b.iter(|| Lexer::new(black_box(&one_line)).count())
+    });
+    // Peeking at every token before taking it, as the parser does: from the
+    // lookahead buffer, and by cloning the lexer and lexing each token twice
+    // as the parser did before the buffer.
+    group.bench_function("peek buffered", |b| {
+        b.iter(|| {
+            let mut lexer = Lexer::new(black_box(&source));
+            while lexer.peek().is_some() {
+                lexer.next();
+            }
+        })
+    });
+    group.bench_function("peek by cloning", |b| {
+        b.iter(|| {
+            let mut lexer = Lexer::new(black_box(&source));
+            while lexer.clone().next().is_some() {
+                lexer.next();
+            }
+        })
+    });
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            let (program, errors) =
+                Parser::with_lexer(Lexer::new(black_box(&source))).parse_program();
+            assert!(errors.is_empty());
+            program
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,462 @@
+use serde::Serialize;
+
+use crate::{
+    error::{Error, LexingErrorKind, ParseErrorKind},
+    source_map::SourceMap,
+    token::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A message attached to a range of the source code.
+#[derive(Debug, Clone, Serialize)]
+pub struct Label {
+    span: Span,
+    message: String,
+    primary: bool,
+}
+
+impl Label {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+}
+
+/// A message about the source code, along with the places it points at and
+/// any help on fixing it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: Option<String>,
+    message: String,
+    labels: Vec<Label>,
+    help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Points the diagnostic at `span`. The message may be empty.
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    /// Adds a related location, such as an earlier declaration.
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    pub fn help(&self) -> &[String] {
+        &self.help
+    }
+
+    /// The span of the first primary label, if any.
+    pub fn span(&self) -> Option<Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .map(|label| label.span)
+    }
+}
+
+/// The jlox-style messages start with where the error is, as in
+/// `Error at 'x': Expect expression.`, which the snippet already shows.
+fn strip_location(message: &str) -> &str {
+    match message.split_once(": ") {
+        Some((location, message)) if location.starts_with("Error") => message,
+        _ => message,
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::UnexpectedEndOfInput(span) => {
+                Diagnostic::error("Unexpected end of input.").with_primary(*span, "expected more")
+            }
+            Error::ParseError(e) => {
+                let message = e.kind().to_string();
+                let mut diagnostic = Diagnostic::error(strip_location(&message));
+                let label = match e.kind() {
+                    ParseErrorKind::InvalidExpression(_) => "expected an expression",
+                    ParseErrorKind::InvalidAssignmentTarget => "can't assign to this",
+                    ParseErrorKind::Redeclaration(_) => "already declared in this scope",
+                    _ => "",
+                };
+                diagnostic = diagnostic.with_primary(e.span(), label);
+                if let ParseErrorKind::ReadInOwnInitializer(name) = e.kind() {
+                    diagnostic = diagnostic.with_help(format!(
+                        "'{name}' is only in scope once its initializer has run"
+                    ));
+                }
+                diagnostic
+            }
+            Error::LexingError(e) => {
+                let diagnostic = Diagnostic::error(e.kind().to_string());
+                let label = match e.kind() {
+                    LexingErrorKind::UnexpectedToken { expected, .. } => {
+                        format!("expected {expected}")
+                    }
+                    LexingErrorKind::UnterminatedString => "string starts here".to_string(),
+                    LexingErrorKind::InvalidNumber(_) => "invalid number".to_string(),
+                    LexingErrorKind::UnterminatedComment { .. } => {
+                        "comment starts here".to_string()
+                    }
+                    LexingErrorKind::InvalidEscape(_) => {
+                        return diagnostic
+                            .with_primary(e.span(), "unknown escape")
+                            .with_help(r#"valid escapes are \n \t \r \\ \" \$ \0 and \u{XXXX}"#);
+                    }
+                    _ => String::new(),
+                };
+                diagnostic.with_primary(e.span(), label)
+            }
+            Error::RuntimeError(e) => {
+                Diagnostic::error(e.kind().to_string()).with_primary(e.span(), "")
+            }
+        }
+    }
+}
+
+/// How diagnostics are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// The message followed by the source lines it points at.
+    #[default]
+    Human,
+    /// One `file:line:column: severity: message` line per diagnostic.
+    Short,
+    /// One JSON object per line.
+    Json,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders diagnostics about a single source file.
+pub struct Renderer<'a> {
+    source_map: SourceMap<'a>,
+    path: &'a str,
+    format: Format,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source: &'a str, path: &'a str, format: Format) -> Self {
+        Self {
+            source_map: SourceMap::new(source),
+            path,
+            format,
+            color: false,
+        }
+    }
+
+    /// Enables ANSI colours in the human and short formats.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match self.format {
+            Format::Human => self.render_human(diagnostic),
+            Format::Short => self.render_short(diagnostic),
+            Format::Json => self.render_json(diagnostic),
+        }
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color && !text.is_empty() {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn severity_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+        }
+    }
+
+    fn header(&self, diagnostic: &Diagnostic) -> String {
+        let severity = match diagnostic.code() {
+            Some(code) => format!("{}[{code}]", diagnostic.severity()),
+            None => diagnostic.severity().to_string(),
+        };
+        format!(
+            "{}{}",
+            self.paint(&severity, Self::severity_style(diagnostic.severity())),
+            self.paint(&format!(": {}", diagnostic.message()), BOLD)
+        )
+    }
+
+    fn render_short(&self, diagnostic: &Diagnostic) -> String {
+        let location = match diagnostic.span() {
+            Some(span) => format!("{}:{}:{}", self.path, span.line(), span.column()),
+            None => self.path.to_string(),
+        };
+        format!("{location}: {}", self.header(diagnostic))
+    }
+
+    fn render_json(&self, diagnostic: &Diagnostic) -> String {
+        #[derive(Serialize)]
+        struct Json<'a> {
+            file: &'a str,
+            #[serde(flatten)]
+            diagnostic: &'a Diagnostic,
+        }
+
+        serde_json::to_string(&Json {
+            file: self.path,
+            diagnostic,
+        })
+        .expect("diagnostics always serialize")
+    }
+
+    fn render_human(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = self.header(diagnostic);
+
+        let mut labels = diagnostic.labels().iter().collect::<Vec<_>>();
+        labels.sort_by_key(|label| (label.span.line(), !label.primary));
+
+        let width = labels
+            .iter()
+            .map(|label| label.span.line().to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = self.paint(&format!("{:width$} |", ""), BLUE);
+
+        if let Some(span) = diagnostic.span().or(labels.first().map(|label| label.span)) {
+            out += &format!(
+                "\n{}{} {}:{}:{}",
+                " ".repeat(width),
+                self.paint("-->", BLUE),
+                self.path,
+                span.line(),
+                span.column()
+            );
+            out += &format!("\n{gutter}");
+        }
+
+        let mut last_line = None;
+        for label in &labels {
+            let line = label.span.line();
+            let source = self.source_map.source();
+            let range = self
+                .source_map
+                .line_range(line)
+                .unwrap_or(source.len()..source.len());
+            let text = &source[range.clone()];
+            if last_line != Some(line) {
+                if last_line.is_some_and(|last| line > last + 1) {
+                    out += &format!("\n{}", self.paint("...", BLUE));
+                }
+                out += &format!(
+                    "\n{} {}",
+                    self.paint(&format!("{line:>width$} |"), BLUE),
+                    text
+                );
+                last_line = Some(line);
+            }
+
+            // Keep tabs so the underline lines up with the source line.
+            let start = label.span.start().clamp(range.start, range.end);
+            let end = label.span.end().clamp(start, range.end);
+            let indent = source[range.start..start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let len = source[start..end].chars().count().max(1);
+            let (marker, style) = if label.primary {
+                ("^", Self::severity_style(diagnostic.severity()))
+            } else {
+                ("-", BLUE)
+            };
+            let underline = format!("{} {}", marker.repeat(len), label.message);
+            out += &format!(
+                "\n{gutter} {indent}{}",
+                self.paint(underline.trim_end(), style)
+            );
+        }
+
+        for help in diagnostic.help() {
+            out += &format!("\n{:width$} {} {help}", "", self.paint("= help:", BOLD));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Lexer, Parser};
+
+    fn parse_error(input: &str) -> Diagnostic {
+        let (_, errors) = Parser::with_lexer(Lexer::new(input)).parse_program();
+        Diagnostic::from(&errors[0])
+    }
+
+    #[test]
+    fn human() {
+        let input = "var a = 1;\nprint a +;\n";
+        let diagnostic = parse_error(input);
+        let rendered = Renderer::new(input, "test.lox", Format::Human).render(&diagnostic);
+        assert_eq!(
+            rendered,
+            "error: Expect expression.
+ --> test.lox:2:10
+  |
+2 | print a +;
+  |          ^ expected an expression"
+        );
+    }
+
+    #[test]
+    fn labels_and_help() {
+        let input = "var a = 1;\n\n\nvar a = 2;";
+        let diagnostic = Diagnostic::new(Severity::Warning, "Shadowed variable.")
+            .with_code("L001")
+            .with_primary(Span::new(17, 18, 4, 5), "shadows a variable")
+            .with_secondary(Span::new(4, 5, 1, 5), "declared here")
+            .with_help("rename one of them");
+        let rendered = Renderer::new(input, "test.lox", Format::Human).render(&diagnostic);
+        assert_eq!(
+            rendered,
+            "warning[L001]: Shadowed variable.
+ --> test.lox:4:5
+  |
+1 | var a = 1;
+  |     - declared here
+...
+4 | var a = 2;
+  |     ^ shadows a variable
+  = help: rename one of them"
+        );
+    }
+
+    #[test]
+    fn end_of_input() {
+        let input = "{\n  print 1;";
+        let diagnostic = parse_error(input);
+        let rendered = Renderer::new(input, "test.lox", Format::Human).render(&diagnostic);
+        assert_eq!(
+            rendered,
+            "error: Unexpected end of input.
+ --> test.lox:2:11
+  |
+2 |   print 1;
+  |           ^ expected more"
+        );
+    }
+
+    #[test]
+    fn short_and_json() {
+        let input = "print @;";
+        let diagnostic = parse_error(input);
+
+        let rendered = Renderer::new(input, "test.lox", Format::Short).render(&diagnostic);
+        assert_eq!(rendered, "test.lox:1:7: error: Unexpected character: @");
+
+        let rendered = Renderer::new(input, "test.lox", Format::Json).render(&diagnostic);
+        let json: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(json["file"], "test.lox");
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["message"], "Unexpected character: @");
+        assert_eq!(json["labels"][0]["span"]["start"], 6);
+        assert_eq!(json["labels"][0]["span"]["column"], 7);
+        assert_eq!(json["labels"][0]["primary"], true);
+    }
+
+    #[test]
+    fn color() {
+        let input = "-nil;";
+        let diagnostic =
+            Diagnostic::error("Operand must be a number.").with_primary(Span::new(0, 4, 1, 1), "");
+        let rendered = Renderer::new(input, "test.lox", Format::Short)
+            .with_color(true)
+            .render(&diagnostic);
+        assert_eq!(
+            rendered,
+            "test.lox:1:1: \x1b[1;31merror\x1b[0m\x1b[1m: Operand must be a number.\x1b[0m"
+        );
+    }
+}
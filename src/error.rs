@@ -1,42 +1,40 @@
 use std::error;
 
-use crate::token::{Keyword, Operator, TokenType};
+use crate::token::{Keyword, Operator, Span, TokenType};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Error {
-    UnexpectedEndOfInput,
+    UnexpectedEndOfInput(Span),
     ParseError(ParseError),
     LexingError(LexingError),
+    RuntimeError(RuntimeError),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParseError {
     kind: ParseErrorKind,
-    line: Option<usize>,
+    span: Span,
 }
 
 impl ParseError {
-    pub fn new(kind: ParseErrorKind) -> Self {
-        Self { kind, line: None }
+    pub fn new(kind: ParseErrorKind, span: Span) -> Self {
+        Self { kind, span }
     }
 
     pub fn kind(&self) -> &ParseErrorKind {
         &self.kind
     }
 
-    pub fn line(&self) -> Option<usize> {
-        self.line
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    pub fn with_line(kind: ParseErrorKind, line: usize) -> Self {
-        Self {
-            kind,
-            line: Some(line),
-        }
+    pub fn line(&self) -> usize {
+        self.span.line()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParseErrorKind {
     UnsupportedOperator(Operator),
     UnsupportedKeyword(Keyword),
@@ -44,6 +42,17 @@ pub enum ParseErrorKind {
     UnexpectedKeyword(Keyword),
     UnexpectedToken(TokenType, String),
     InvalidExpression(String),
+    InvalidAssignmentTarget,
+    TooManyArguments,
+    TooManyParameters,
+    InheritFromSelf(String),
+    SuperOutsideClass,
+    SuperWithoutSuperclass,
+    ReadInOwnInitializer(String),
+    Redeclaration(String),
+    ReturnAtTopLevel,
+    ReturnFromInitializer,
+    ThisOutsideClass,
 }
 
 impl std::fmt::Display for ParseErrorKind {
@@ -59,43 +68,85 @@ impl std::fmt::Display for ParseErrorKind {
             ParseErrorKind::InvalidExpression(lexeme) => {
                 write!(f, "Error at '{lexeme}': Expect expression.")
             }
+            ParseErrorKind::InvalidAssignmentTarget => {
+                write!(f, "Error at '=': Invalid assignment target.")
+            }
+            ParseErrorKind::TooManyArguments => {
+                write!(f, "Error: Can't have more than 255 arguments.")
+            }
+            ParseErrorKind::TooManyParameters => {
+                write!(f, "Error: Can't have more than 255 parameters.")
+            }
+            ParseErrorKind::InheritFromSelf(name) => {
+                write!(f, "Error at '{name}': A class can't inherit from itself.")
+            }
+            ParseErrorKind::SuperOutsideClass => {
+                write!(f, "Error at 'super': Can't use 'super' outside of a class.")
+            }
+            ParseErrorKind::SuperWithoutSuperclass => {
+                write!(
+                    f,
+                    "Error at 'super': Can't use 'super' in a class with no superclass."
+                )
+            }
+            ParseErrorKind::ReadInOwnInitializer(name) => write!(
+                f,
+                "Error at '{name}': Can't read local variable in its own initializer."
+            ),
+            ParseErrorKind::Redeclaration(name) => write!(
+                f,
+                "Error at '{name}': Already a variable with this name in this scope."
+            ),
+            ParseErrorKind::ReturnAtTopLevel => {
+                write!(f, "Error at 'return': Can't return from top-level code.")
+            }
+            ParseErrorKind::ReturnFromInitializer => write!(
+                f,
+                "Error at 'return': Can't return a value from an initializer."
+            ),
+            ParseErrorKind::ThisOutsideClass => {
+                write!(f, "Error at 'this': Can't use 'this' outside of a class.")
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LexingError {
     kind: LexingErrorKind,
-    line: Option<usize>,
+    span: Span,
 }
 
 impl LexingError {
-    pub fn new(kind: LexingErrorKind) -> Self {
-        Self { kind, line: None }
+    pub fn new(kind: LexingErrorKind, span: Span) -> Self {
+        Self { kind, span }
     }
 
     pub fn kind(&self) -> &LexingErrorKind {
         &self.kind
     }
 
-    pub fn line(&self) -> Option<usize> {
-        self.line
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    pub fn with_line(kind: LexingErrorKind, line: usize) -> Self {
-        Self {
-            kind,
-            line: Some(line),
-        }
+    pub fn line(&self) -> usize {
+        self.span.line()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum LexingErrorKind {
     InvalidOperator(String),
     InvalidLiteral(String),
     InvalidKeyword(String),
     UnterminatedString,
+    InvalidEscape(String),
+    InvalidNumber(String),
+    UnterminatedComment {
+        /// The line the comment starts on.
+        line: usize,
+    },
     UnexpectedCharacter(char),
     UnexpectedToken {
         expected: TokenType,
@@ -113,29 +164,115 @@ impl std::fmt::Display for LexingErrorKind {
                 write!(f, "Unexpected Token: Expected {expected}, found {found}.")
             }
             LexingErrorKind::UnterminatedString => write!(f, "Unterminated string."),
+            LexingErrorKind::UnterminatedComment { line } => {
+                write!(f, "Unterminated block comment starting on line {line}.")
+            }
+            LexingErrorKind::InvalidNumber(number) => {
+                write!(f, "Invalid number literal: {number}")
+            }
+            LexingErrorKind::InvalidEscape(escape) => {
+                write!(f, "Invalid escape sequence: {escape}")
+            }
             LexingErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character: {c}"),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    kind: RuntimeErrorKind,
+    span: Span,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn kind(&self) -> &RuntimeErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn line(&self) -> usize {
+        self.span.line()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RuntimeErrorKind {
+    OperandMustBeNumber,
+    OperandsMustBeNumbers,
+    OperandsMustBeNumbersOrStrings,
+    UndefinedVariable(String),
+    NotCallable,
+    OnlyInstancesHaveProperties,
+    OnlyInstancesHaveFields,
+    UndefinedProperty(String),
+    SuperclassMustBeClass,
+    ArityMismatch { expected: usize, found: usize },
+    UnsupportedExpression(String),
+    StackOverflow,
+}
+
+impl std::fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeErrorKind::OperandMustBeNumber => write!(f, "Operand must be a number."),
+            RuntimeErrorKind::OperandsMustBeNumbers => write!(f, "Operands must be numbers."),
+            RuntimeErrorKind::OperandsMustBeNumbersOrStrings => {
+                write!(f, "Operands must be two numbers or two strings.")
+            }
+            RuntimeErrorKind::UndefinedVariable(name) => {
+                write!(f, "Undefined variable '{name}'.")
+            }
+            RuntimeErrorKind::NotCallable => write!(f, "Can only call functions and classes."),
+            RuntimeErrorKind::OnlyInstancesHaveProperties => {
+                write!(f, "Only instances have properties.")
+            }
+            RuntimeErrorKind::OnlyInstancesHaveFields => write!(f, "Only instances have fields."),
+            RuntimeErrorKind::UndefinedProperty(name) => {
+                write!(f, "Undefined property '{name}'.")
+            }
+            RuntimeErrorKind::SuperclassMustBeClass => write!(f, "Superclass must be a class."),
+            RuntimeErrorKind::ArityMismatch { expected, found } => {
+                write!(f, "Expected {expected} arguments but got {found}.")
+            }
+            RuntimeErrorKind::UnsupportedExpression(expr) => {
+                write!(f, "Unsupported expression: {expr}")
+            }
+            RuntimeErrorKind::StackOverflow => write!(f, "Stack overflow."),
+        }
+    }
+}
+
+impl Error {
+    /// The location in the source code the error points at.
+    pub fn span(&self) -> Span {
+        match self {
+            Error::UnexpectedEndOfInput(span) => *span,
+            Error::ParseError(e) => e.span,
+            Error::LexingError(e) => e.span,
+            Error::RuntimeError(e) => e.span,
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let msg = match self {
-            Error::UnexpectedEndOfInput => "Unexpected end of input".to_string(),
-            Error::ParseError(e) => {
-                if e.line.is_some() {
-                    format!("[line {}] {}", e.line.unwrap(), e.kind)
-                } else {
-                    format!("{}", e.kind)
-                }
-            }
-            Error::LexingError(e) => {
-                if e.line.is_some() {
-                    format!("[line {}] Error: {}", e.line.unwrap(), e.kind)
-                } else {
-                    format!("Error: {}", e.kind)
-                }
+            Error::UnexpectedEndOfInput(span) => {
+                format!(
+                    "[line {}] Error at end: Unexpected end of input",
+                    span.line()
+                )
             }
+            Error::ParseError(e) => format!("[line {}] {}", e.line(), e.kind),
+            Error::LexingError(e) => format!("[line {}] Error: {}", e.line(), e.kind),
+            Error::RuntimeError(e) => format!("{}\n[line {}]", e.kind, e.line()),
         };
 
         write!(f, "{}", msg)
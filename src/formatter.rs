@@ -0,0 +1,478 @@
+use crate::{
+    Lexer, Parser,
+    error::Error,
+    token::{Keyword, Literal, Operator, Token, TokenType, UnaryOperator},
+};
+
+const INDENT: &str = "    ";
+
+/// Lays Lox source code out in a canonical style: one statement per line,
+/// blocks indented by four spaces with the opening brace on the same line,
+/// single spaces around binary operators and at most one blank line in a row.
+///
+/// Works on tokens rather than the syntax tree, so comments and the spelling
+/// of literals are kept as they are.
+#[derive(Debug, Clone, Copy)]
+pub struct Formatter {
+    /// The line width past which argument and parameter lists are wrapped.
+    width: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter {
+    pub fn new() -> Self {
+        Self { width: 100 }
+    }
+
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Formats `source`, which must parse without errors.
+    pub fn format(&self, source: &str) -> Result<String, Vec<Error>> {
+        let (_, errors) = Parser::with_lexer(Lexer::new(source)).parse_program();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let tokens = Lexer::new(source)
+            .with_comments()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| vec![e])?;
+
+        let mut layout = Layout {
+            width: self.width,
+            out: String::with_capacity(source.len()),
+            line: Vec::new(),
+            line_indent: 0,
+            indent: 0,
+            blank_line: false,
+            break_pending: false,
+        };
+        // The last token that isn't a comment, and whether it was a prefix
+        // operator.
+        let mut previous: Option<(Token, bool)> = None;
+        // The line the previous token ended on.
+        let mut last_line = None;
+        let mut parens = 0usize;
+
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            let gap = last_line.map_or(0, |last| token.span().line() - last);
+            last_line = Some(end_line(token));
+            layout.blank_line = gap > 1;
+
+            if layout.break_pending {
+                let trailing_comment = token.ty() == TokenType::Comment && gap == 0;
+                let else_after_brace = token.ty() == TokenType::Keyword(Keyword::Else)
+                    && previous.is_some_and(|(previous, _)| is_punct(previous, RIGHT_BRACE));
+                if else_after_brace {
+                    layout.break_pending = false;
+                } else if !trailing_comment {
+                    layout.end_line();
+                }
+            }
+
+            if token.ty() == TokenType::Comment {
+                let comment = token.lexeme().trim_end();
+                if !layout.line.is_empty() && gap == 0 {
+                    layout.push(comment, true, Kind::Other);
+                } else {
+                    layout.end_line();
+                    layout.push(comment, false, Kind::Other);
+                    // A block comment stays in front of code on the same line.
+                    let next_line = tokens.peek().map(|next| next.span().line());
+                    if next_line != last_line {
+                        layout.end_line();
+                    }
+                }
+                if comment.starts_with("//") {
+                    layout.end_line();
+                }
+                continue;
+            }
+
+            let prefix = is_prefix(token, previous.map(|(previous, _)| previous));
+            let space = previous.is_some_and(|(previous, previous_prefix)| {
+                !previous_prefix && space_between(previous, token)
+            });
+            previous = Some((token, prefix));
+
+            if is_punct(token, LEFT_BRACE) {
+                layout.push(token.lexeme(), space, Kind::Other);
+                if let Some(close) = tokens.next_if(|next| is_punct(*next, RIGHT_BRACE)) {
+                    layout.push(close.lexeme(), false, Kind::Other);
+                    previous = Some((close, false));
+                    last_line = Some(end_line(close));
+                } else {
+                    layout.indent += 1;
+                }
+                layout.break_pending = true;
+            } else if is_punct(token, RIGHT_BRACE) {
+                layout.end_line();
+                layout.indent = layout.indent.saturating_sub(1);
+                layout.push(token.lexeme(), false, Kind::Other);
+                layout.break_pending = true;
+            } else {
+                let kind = match token.ty() {
+                    TokenType::Operator(Operator::Unary(UnaryOperator::LeftParen)) => {
+                        parens += 1;
+                        Kind::Open { paren: true }
+                    }
+                    TokenType::Operator(Operator::Unary(UnaryOperator::RightParen)) => {
+                        parens = parens.saturating_sub(1);
+                        Kind::Close
+                    }
+                    TokenType::Operator(Operator::Unary(UnaryOperator::Comma)) => Kind::Comma,
+                    TokenType::Literal(Literal::Interpolation)
+                        if token.lexeme().starts_with('"') =>
+                    {
+                        Kind::Open { paren: false }
+                    }
+                    TokenType::Literal(Literal::String) if token.lexeme().starts_with('}') => {
+                        Kind::Close
+                    }
+                    _ => Kind::Other,
+                };
+                layout.push(token.lexeme(), space, kind);
+                // Semicolons in a `for` loop's header don't end the line.
+                if is_punct(token, SEMICOLON) && parens == 0 {
+                    layout.break_pending = true;
+                }
+            }
+        }
+        layout.end_line();
+
+        Ok(layout.out)
+    }
+}
+
+const LEFT_BRACE: UnaryOperator = UnaryOperator::LeftBrace;
+const RIGHT_BRACE: UnaryOperator = UnaryOperator::RightBrace;
+const SEMICOLON: UnaryOperator = UnaryOperator::Selmicolon;
+
+fn end_line(token: Token) -> usize {
+    token.span().line() + token.lexeme().matches('\n').count()
+}
+
+fn is_punct(token: Token, punct: UnaryOperator) -> bool {
+    token.ty() == TokenType::Operator(Operator::Unary(punct))
+}
+
+/// Returns `true` if `token` can be the last token of an operand, so an
+/// operator after it is binary or postfix.
+fn ends_operand(token: Token) -> bool {
+    match token.ty() {
+        TokenType::Literal(Literal::Interpolation) => false,
+        TokenType::Literal(_) => true,
+        TokenType::Keyword(keyword) => matches!(
+            keyword,
+            Keyword::True | Keyword::False | Keyword::Nil | Keyword::This | Keyword::Super
+        ),
+        TokenType::Operator(Operator::Unary(op)) => op == UnaryOperator::RightParen,
+        _ => false,
+    }
+}
+
+/// Returns `true` if `token` is a `-`, `+` or `!` applied to the operand
+/// after it.
+fn is_prefix(token: Token, previous: Option<Token>) -> bool {
+    matches!(
+        token.ty(),
+        TokenType::Operator(Operator::Unary(
+            UnaryOperator::Minus | UnaryOperator::Plus | UnaryOperator::Bang
+        ))
+    ) && !previous.is_some_and(ends_operand)
+}
+
+/// Whether a space goes between two tokens on the same line, unless the first
+/// is a prefix operator.
+fn space_between(previous: Token, next: Token) -> bool {
+    use UnaryOperator::*;
+
+    let punct = |token: Token| match token.ty() {
+        TokenType::Operator(Operator::Unary(op)) => Some(op),
+        _ => None,
+    };
+    match (punct(previous), punct(next)) {
+        (_, Some(RightParen | Comma | Selmicolon | Dot)) => return false,
+        (Some(LeftParen | Dot), _) => return false,
+        (Some(LeftBrace), Some(RightBrace)) => return false,
+        // A postfix operator.
+        (_, Some(Bang)) if ends_operand(previous) => return false,
+        // A call.
+        (_, Some(LeftParen)) => return !ends_operand(previous),
+        _ => {}
+    }
+
+    // The expressions embedded in a string hug its parts.
+    let previous_part = previous.ty() == TokenType::Literal(Literal::Interpolation);
+    let next_part = matches!(
+        next.ty(),
+        TokenType::Literal(Literal::String | Literal::Interpolation)
+    ) && next.lexeme().starts_with('}');
+    !previous_part && !next_part
+}
+
+/// How a token affects where a line can be wrapped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Opens a group, either a `(` or the start of a string with embedded
+    /// expressions. Lines are only wrapped inside parentheses.
+    Open {
+        paren: bool,
+    },
+    Close,
+    Comma,
+    Other,
+}
+
+#[derive(Debug)]
+struct Piece<'a> {
+    text: &'a str,
+    /// Whether there is a space before the piece, unless it starts the line.
+    space: bool,
+    kind: Kind,
+}
+
+struct Layout<'a> {
+    width: usize,
+    out: String,
+    /// The pieces of the line being built.
+    line: Vec<Piece<'a>>,
+    /// The indentation level the line being built started at.
+    line_indent: usize,
+    indent: usize,
+    /// Whether the source has a blank line before the current token.
+    blank_line: bool,
+    /// Whether the line ends before the next token, unless that is a trailing
+    /// comment or an `else`.
+    break_pending: bool,
+}
+
+impl<'a> Layout<'a> {
+    fn push(&mut self, text: &'a str, space: bool, kind: Kind) {
+        if self.line.is_empty() {
+            self.line_indent = self.indent;
+            if self.blank_line && !self.out.is_empty() && !self.out.ends_with("{\n") && text != "}"
+            {
+                self.out.push('\n');
+            }
+        }
+        self.line.push(Piece { text, space, kind });
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if !line.is_empty() {
+            self.write_line(&line, self.line_indent);
+        }
+        self.break_pending = false;
+    }
+
+    fn write_line(&mut self, pieces: &[Piece], indent: usize) {
+        let mut text = String::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            if i > 0 && piece.space {
+                text.push(' ');
+            }
+            text.push_str(piece.text);
+        }
+
+        if indent * INDENT.len() + text.chars().count() > self.width && self.wrap(pieces, indent) {
+            return;
+        }
+        for _ in 0..indent {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(&text);
+        self.out.push('\n');
+    }
+
+    /// Writes a line that is too wide with the items of its first
+    /// parenthesized list on lines of their own, returning `false` if it
+    /// has no list to wrap.
+    fn wrap(&mut self, pieces: &[Piece], indent: usize) -> bool {
+        let mut depth = 0usize;
+        // The start of the outermost group and whether it has several items.
+        let mut group = None;
+        let mut list = None;
+        for (i, piece) in pieces.iter().enumerate() {
+            match piece.kind {
+                Kind::Open { paren } => {
+                    if depth == 0 && paren {
+                        group = Some((i, false));
+                    }
+                    depth += 1;
+                }
+                Kind::Close if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        if let Some((start, true)) = group {
+                            list = Some((start, i));
+                            break;
+                        }
+                        group = None;
+                    }
+                }
+                Kind::Comma if depth == 1 => {
+                    if let Some((_, commas)) = &mut group {
+                        *commas = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let Some((open, close)) = list else {
+            return false;
+        };
+
+        self.write_line(&pieces[..=open], indent);
+        let mut depth = 0usize;
+        let mut item = open + 1;
+        for (i, piece) in pieces.iter().enumerate().take(close).skip(open + 1) {
+            match piece.kind {
+                Kind::Open { .. } => depth += 1,
+                Kind::Close => depth = depth.saturating_sub(1),
+                Kind::Comma if depth == 0 => {
+                    self.write_line(&pieces[item..=i], indent + 1);
+                    item = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if item < close {
+            self.write_line(&pieces[item..close], indent + 1);
+        }
+        self.write_line(&pieces[close..], indent);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        let formatted = Formatter::new().format(source).unwrap();
+        assert_eq!(
+            Formatter::new().format(&formatted).unwrap(),
+            formatted,
+            "Formatting isn't idempotent"
+        );
+        formatted
+    }
+
+    #[test]
+    fn spacing() {
+        assert_eq!(
+            format("var a=-1+2*(b-c)/ -d;print !a==!!b and f (a,b)(c).d;"),
+            "var a = -1 + 2 * (b - c) / -d;\nprint !a == !!b and f(a, b)(c).d;\n"
+        );
+        assert_eq!(
+            format("print a?b:c;print\"x ${ a+1 } y ${b}\";return ;"),
+            "print a ? b : c;\nprint \"x ${a + 1} y ${b}\";\nreturn;\n"
+        );
+        assert_eq!(format("print 0x1_0 + 1.50;"), "print 0x1_0 + 1.50;\n");
+    }
+
+    #[test]
+    fn blocks() {
+        let source = "class A<B{init(a){this.a=a;}f(){}}
+fun g(){if(a){print 1;}else if(b)print 2;else{print 3;}
+for(var i=0;i<3;i=i+1)print i;for(;;){}while(true){return;}}";
+        assert_eq!(
+            format(source),
+            "class A < B {
+    init(a) {
+        this.a = a;
+    }
+    f() {}
+}
+fun g() {
+    if (a) {
+        print 1;
+    } else if (b) print 2;
+    else {
+        print 3;
+    }
+    for (var i = 0; i < 3; i = i + 1) print i;
+    for (;;) {}
+    while (true) {
+        return;
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let source = "// Header
+
+var a; // trailing
+
+
+/* block */ var b;
+{
+
+    // inside
+    print a; /* after */
+
+}
+/*
+ * multi-line
+ */
+";
+        assert_eq!(
+            format(source),
+            "// Header
+
+var a; // trailing
+
+/* block */ var b;
+{
+    // inside
+    print a; /* after */
+}
+/*
+ * multi-line
+ */
+"
+        );
+    }
+
+    #[test]
+    fn wrapping() {
+        let formatter = Formatter::new().with_width(30);
+        assert_eq!(
+            formatter
+                .format("fun f(first, second) { return g(first, h(second, 1), \"${x(1, 2)}\"); }")
+                .unwrap(),
+            "fun f(first, second) {
+    return g(
+        first,
+        h(second, 1),
+        \"${x(1, 2)}\"
+    );
+}
+"
+        );
+        assert_eq!(
+            formatter
+                .format("print aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa;")
+                .unwrap(),
+            "print aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa;\n"
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(Formatter::new().format("print (1;").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{error::Error, token::Function};
+
+use super::{Environment, Interpreter, LoxInstance, Value};
+
+/// Anything that can be invoked with the call syntax `callee(arguments)`.
+pub trait LoxCallable<'a> {
+    /// The number of arguments the callable expects.
+    fn arity(&self) -> usize;
+
+    /// Invokes the callable. The caller has already checked the argument count.
+    fn call(
+        &self,
+        interpreter: &mut Interpreter<'a>,
+        arguments: Vec<Value<'a>>,
+    ) -> Result<Value<'a>, Error>;
+}
+
+/// A function declared in Lox source, along with the scope it was declared in.
+#[derive(Debug)]
+pub struct LoxFunction<'a> {
+    declaration: Rc<Function<'a>>,
+    closure: Rc<RefCell<Environment<'a>>>,
+    is_initializer: bool,
+}
+
+impl<'a> LoxFunction<'a> {
+    pub fn new(
+        declaration: Rc<Function<'a>>,
+        closure: Rc<RefCell<Environment<'a>>>,
+        is_initializer: bool,
+    ) -> Self {
+        Self {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.declaration.name
+    }
+
+    /// Returns a copy of this method whose scope binds `this` to `instance`.
+    pub fn bind(&self, instance: Rc<RefCell<LoxInstance<'a>>>) -> LoxFunction<'a> {
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.closure));
+        scope.define("this", Value::Instance(instance));
+        LoxFunction::new(
+            Rc::clone(&self.declaration),
+            Rc::new(RefCell::new(scope)),
+            self.is_initializer,
+        )
+    }
+}
+
+impl<'a> LoxCallable<'a> for LoxFunction<'a> {
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter<'a>,
+        arguments: Vec<Value<'a>>,
+    ) -> Result<Value<'a>, Error> {
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.closure));
+        for ((param, _), argument) in self.declaration.params.iter().zip(arguments) {
+            scope.define(param, argument);
+        }
+        let value = interpreter.call_function(&self.declaration.body, scope)?;
+
+        // Initializers always hand back the instance, even on an early `return;`.
+        if self.is_initializer {
+            return Ok(self.closure.borrow().get("this").unwrap_or(Value::Nil));
+        }
+        Ok(value)
+    }
+}
+
+/// A function implemented in Rust and exposed to Lox as a global.
+#[derive(Debug)]
+pub struct NativeFunction {
+    name: &'static str,
+    arity: usize,
+    function: for<'v> fn(&[Value<'v>]) -> Value<'v>,
+}
+
+impl NativeFunction {
+    /// Every native function available in the global scope.
+    pub fn all() -> Vec<NativeFunction> {
+        vec![NativeFunction {
+            name: "clock",
+            arity: 0,
+            function: |_| {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("System time is before the UNIX epoch");
+                Value::Number(now.as_secs_f64())
+            },
+        }]
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<'a> LoxCallable<'a> for NativeFunction {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter<'a>,
+        arguments: Vec<Value<'a>>,
+    ) -> Result<Value<'a>, Error> {
+        Ok((self.function)(&arguments))
+    }
+}
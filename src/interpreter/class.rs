@@ -0,0 +1,95 @@
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::error::Error;
+
+use super::{Interpreter, LoxCallable, LoxFunction, Value};
+
+/// A class declared in Lox source. Calling it creates a new instance.
+#[derive(Debug)]
+pub struct LoxClass<'a> {
+    name: Cow<'a, str>,
+    superclass: Option<Rc<LoxClass<'a>>>,
+    methods: HashMap<String, Rc<LoxFunction<'a>>>,
+}
+
+impl<'a> LoxClass<'a> {
+    pub fn new(
+        name: Cow<'a, str>,
+        superclass: Option<Rc<LoxClass<'a>>>,
+        methods: HashMap<String, Rc<LoxFunction<'a>>>,
+    ) -> Self {
+        Self {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Looks up a method on this class and then along its superclass chain.
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction<'a>>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+}
+
+impl<'a> LoxCallable<'a> for Rc<LoxClass<'a>> {
+    fn arity(&self) -> usize {
+        self.find_method("init")
+            .map_or(0, |initializer| initializer.arity())
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter<'a>,
+        arguments: Vec<Value<'a>>,
+    ) -> Result<Value<'a>, Error> {
+        let instance = Rc::new(RefCell::new(LoxInstance::new(Rc::clone(self))));
+        if let Some(initializer) = self.find_method("init") {
+            initializer
+                .bind(Rc::clone(&instance))
+                .call(interpreter, arguments)?;
+        }
+        Ok(Value::Instance(instance))
+    }
+}
+
+/// An instance of a [`LoxClass`] with its own set of fields.
+#[derive(Debug)]
+pub struct LoxInstance<'a> {
+    class: Rc<LoxClass<'a>>,
+    fields: HashMap<String, Value<'a>>,
+}
+
+impl<'a> LoxInstance<'a> {
+    pub fn new(class: Rc<LoxClass<'a>>) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    pub fn class(&self) -> &Rc<LoxClass<'a>> {
+        &self.class
+    }
+
+    /// Looks up a field, falling back to a method bound to `instance`.
+    ///
+    /// Fields shadow methods with the same name.
+    pub fn get(instance: &Rc<RefCell<Self>>, name: &str) -> Option<Value<'a>> {
+        if let Some(value) = instance.borrow().fields.get(name) {
+            return Some(value.clone());
+        }
+        let method = instance.borrow().class.find_method(name)?;
+        Some(Value::Function(Rc::new(method.bind(Rc::clone(instance)))))
+    }
+
+    pub fn set(&mut self, name: &str, value: Value<'a>) {
+        self.fields.insert(name.to_string(), value);
+    }
+}
@@ -0,0 +1,125 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use super::Value;
+
+/// A single scope of variable bindings, linked to the scope it is nested in.
+#[derive(Debug, Default)]
+pub struct Environment<'a> {
+    values: HashMap<String, Value<'a>>,
+    enclosing: Option<Rc<RefCell<Environment<'a>>>>,
+}
+
+impl<'a> Environment<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment<'a>>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Binds `name` in this scope, shadowing any previous binding with the same name.
+    pub fn define(&mut self, name: &str, value: Value<'a>) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    /// Looks `name` up in this scope and then in every enclosing one.
+    pub fn get(&self, name: &str) -> Option<Value<'a>> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Updates the nearest existing binding of `name`.
+    ///
+    /// Returns `false` if `name` is not bound in any scope.
+    pub fn assign(&mut self, name: &str, value: Value<'a>) -> bool {
+        match self.values.get_mut(name) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+                None => false,
+            },
+        }
+    }
+
+    /// Returns the scope `distance` levels up the chain from `environment`.
+    fn ancestor(environment: &Rc<RefCell<Self>>, distance: usize) -> Rc<RefCell<Self>> {
+        let mut environment = Rc::clone(environment);
+        for _ in 0..distance {
+            let enclosing = environment
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("Resolved scope distance exceeds the scope chain");
+            environment = enclosing;
+        }
+        environment
+    }
+
+    /// Looks `name` up exactly `distance` scopes away from `environment`.
+    pub fn get_at(
+        environment: &Rc<RefCell<Self>>,
+        distance: usize,
+        name: &str,
+    ) -> Option<Value<'a>> {
+        Self::ancestor(environment, distance)
+            .borrow()
+            .values
+            .get(name)
+            .cloned()
+    }
+
+    /// Updates the binding of `name` exactly `distance` scopes away from `environment`.
+    ///
+    /// The resolver guarantees the binding exists, so a missing one is a bug.
+    pub fn assign_at(
+        environment: &Rc<RefCell<Self>>,
+        distance: usize,
+        name: &str,
+        value: Value<'a>,
+    ) {
+        let ancestor = Self::ancestor(environment, distance);
+        let mut ancestor = ancestor.borrow_mut();
+        let slot = ancestor
+            .values
+            .get_mut(name)
+            .expect("Resolved variable is not bound at its scope distance");
+        *slot = value;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assign_at() {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        globals.borrow_mut().define("a", Value::Number(1.0));
+        let local = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(
+            &globals,
+        ))));
+
+        Environment::assign_at(&local, 1, "a", Value::Number(2.0));
+        assert_eq!(globals.borrow().get("a"), Some(Value::Number(2.0)));
+        assert!(local.borrow().values.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "not bound at its scope distance")]
+    fn assign_at_wrong_distance() {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        globals.borrow_mut().define("a", Value::Number(1.0));
+        let local = Rc::new(RefCell::new(Environment::with_enclosing(globals)));
+
+        Environment::assign_at(&local, 0, "a", Value::Number(2.0));
+    }
+}
@@ -0,0 +1,947 @@
+mod callable;
+mod class;
+mod environment;
+mod value;
+
+pub use callable::*;
+pub use class::*;
+pub use environment::*;
+pub use value::*;
+
+use std::{cell::RefCell, collections::HashMap, io::Write, ops::ControlFlow, rc::Rc};
+
+use crate::{
+    error::{Error, RuntimeError, RuntimeErrorKind},
+    resolver::Locals,
+    token::{Atom, Op, Program, Span, Stmt, TokenTree},
+};
+
+/// The outcome of executing a statement: either carry on with the next one or
+/// unwind to the enclosing call with the returned value.
+type Flow<'a> = ControlFlow<Value<'a>>;
+
+/// How deeply calls may nest before the interpreter reports a stack overflow
+/// instead of exhausting its own stack.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// The stack size a thread needs to run an [`Interpreter`] up to
+/// `MAX_CALL_DEPTH` nested calls, with room to spare for unoptimized builds.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+pub struct Interpreter<'a> {
+    output: Box<dyn Write>,
+    globals: Rc<RefCell<Environment<'a>>>,
+    environment: Rc<RefCell<Environment<'a>>>,
+    locals: Locals,
+    /// The number of calls currently being executed.
+    call_depth: usize,
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new() -> Self {
+        Self::with_output(Box::new(std::io::stdout()))
+    }
+
+    /// Creates an interpreter that writes the output of `print` statements to `output`.
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        let mut globals = Environment::new();
+        for native in NativeFunction::all() {
+            globals.define(native.name(), Value::NativeFunction(Rc::new(native)));
+        }
+        let globals = Rc::new(RefCell::new(globals));
+
+        Self {
+            output,
+            environment: Rc::clone(&globals),
+            globals,
+            locals: Locals::default(),
+            call_depth: 0,
+        }
+    }
+
+    /// Registers the scope distances computed by the [`Resolver`](crate::Resolver)
+    /// for a program that is about to be interpreted.
+    ///
+    /// Variables without a resolved distance are looked up in the global scope.
+    pub fn add_locals(&mut self, locals: Locals) {
+        self.locals.extend(locals);
+    }
+
+    pub fn interpret(&mut self, program: &Program<'a>) -> Result<(), Error> {
+        for stmt in program.declarations() {
+            if self.execute(stmt)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, stmt: &Stmt<'a>) -> Result<Flow<'a>, Error> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.evaluate(expr)?;
+            }
+            Stmt::Print(expr) => {
+                let value = self.evaluate(expr)?;
+                writeln!(self.output, "{}", value).expect("Failed to write output");
+            }
+            Stmt::Block(stmts) => return self.execute_block(stmts),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    return self.execute(then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    return self.execute(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                while self.evaluate(condition)?.is_truthy() {
+                    if let flow @ Flow::Break(_) = self.execute(body)? {
+                        return Ok(flow);
+                    }
+                }
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                return self.in_scope(|interpreter| {
+                    if let Some(initializer) = initializer
+                        && let flow @ Flow::Break(_) = interpreter.execute(initializer)?
+                    {
+                        return Ok(flow);
+                    }
+                    loop {
+                        if let Some(condition) = condition
+                            && !interpreter.evaluate(condition)?.is_truthy()
+                        {
+                            break;
+                        }
+                        if let flow @ Flow::Break(_) = interpreter.execute(body)? {
+                            return Ok(flow);
+                        }
+                        if let Some(increment) = increment {
+                            interpreter.evaluate(increment)?;
+                        }
+                    }
+                    Ok(Flow::Continue(()))
+                });
+            }
+            Stmt::Var {
+                name, initializer, ..
+            } => {
+                let value = match initializer {
+                    Some(initializer) => self.evaluate(initializer)?,
+                    None => Value::Nil,
+                };
+                self.environment.borrow_mut().define(name, value);
+            }
+            Stmt::Function(declaration) => {
+                let function =
+                    LoxFunction::new(Rc::clone(declaration), Rc::clone(&self.environment), false);
+                self.environment
+                    .borrow_mut()
+                    .define(&declaration.name, Value::Function(Rc::new(function)));
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Break(value));
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                let superclass = match superclass {
+                    Some(superclass) => match self.evaluate(superclass)? {
+                        Value::Class(class) => Some(class),
+                        _ => {
+                            return Err(runtime_error(
+                                RuntimeErrorKind::SuperclassMustBeClass,
+                                superclass.span(),
+                            ));
+                        }
+                    },
+                    None => None,
+                };
+
+                // Methods of a subclass close over a scope that binds `super`.
+                let closure = match &superclass {
+                    Some(superclass) => {
+                        let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                        scope.define("super", Value::Class(Rc::clone(superclass)));
+                        Rc::new(RefCell::new(scope))
+                    }
+                    None => Rc::clone(&self.environment),
+                };
+
+                let methods = methods
+                    .iter()
+                    .map(|method| {
+                        let function = LoxFunction::new(
+                            Rc::clone(method),
+                            Rc::clone(&closure),
+                            method.name == "init",
+                        );
+                        (method.name.to_string(), Rc::new(function))
+                    })
+                    .collect::<HashMap<_, _>>();
+                let class = LoxClass::new(name.clone(), superclass, methods);
+                self.environment
+                    .borrow_mut()
+                    .define(name, Value::Class(Rc::new(class)));
+            }
+        }
+        Ok(Flow::Continue(()))
+    }
+
+    fn execute_block(&mut self, stmts: &[Stmt<'a>]) -> Result<Flow<'a>, Error> {
+        self.in_scope(|interpreter| interpreter.execute_all(stmts))
+    }
+
+    fn execute_all(&mut self, stmts: &[Stmt<'a>]) -> Result<Flow<'a>, Error> {
+        for stmt in stmts {
+            if let flow @ Flow::Break(_) = self.execute(stmt)? {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Continue(()))
+    }
+
+    /// Runs `f` in a fresh scope nested in the current one, restoring the
+    /// current scope afterwards even if `f` fails.
+    fn in_scope<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        let enclosing = Rc::clone(&self.environment);
+        let scope = Environment::with_enclosing(Rc::clone(&enclosing));
+        self.with_environment(Rc::new(RefCell::new(scope)), f)
+    }
+
+    /// Runs `f` with `environment` as the current scope, restoring the
+    /// current scope afterwards even if `f` fails.
+    fn with_environment<T>(
+        &mut self,
+        environment: Rc<RefCell<Environment<'a>>>,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = f(self);
+        self.environment = previous;
+        result
+    }
+
+    pub fn evaluate(&mut self, tree: &TokenTree<'a>) -> Result<Value<'a>, Error> {
+        match tree {
+            TokenTree::Atom(atom, span) => match atom {
+                Atom::String(s) => Ok(Value::String(s.to_string())),
+                Atom::Number(n) => Ok(Value::Number(*n)),
+                Atom::Nil => Ok(Value::Nil),
+                Atom::Bool(b) => Ok(Value::Bool(*b)),
+                Atom::Ident(name, _) => self.look_up_variable(name, tree, *span),
+                Atom::This(_) => self.look_up_variable("this", tree, *span),
+                Atom::Super(_) => Err(runtime_error(
+                    RuntimeErrorKind::UnsupportedExpression(atom.to_string()),
+                    *span,
+                )),
+            },
+            TokenTree::Cons(Op::Group, operands, _) => self.evaluate(&operands[0]),
+            TokenTree::Cons(Op::Equal, operands, span) => match operands.as_slice() {
+                [target @ TokenTree::Atom(Atom::Ident(name, _), _), value] => {
+                    let value = self.evaluate(value)?;
+                    match self.locals.depth(target) {
+                        Some(distance) => {
+                            Environment::assign_at(&self.environment, distance, name, value.clone())
+                        }
+                        None => {
+                            if !self.globals.borrow_mut().assign(name, value.clone()) {
+                                return Err(runtime_error(
+                                    RuntimeErrorKind::UndefinedVariable(name.to_string()),
+                                    *span,
+                                ));
+                            }
+                        }
+                    }
+                    Ok(value)
+                }
+                [TokenTree::Cons(Op::Dot, target, _), value] => {
+                    let [object, TokenTree::Atom(Atom::Ident(name, _), _)] = target.as_slice()
+                    else {
+                        unreachable!("Property accesses are always followed by a name");
+                    };
+                    let Value::Instance(instance) = self.evaluate(object)? else {
+                        return Err(runtime_error(
+                            RuntimeErrorKind::OnlyInstancesHaveFields,
+                            *span,
+                        ));
+                    };
+                    let value = self.evaluate(value)?;
+                    instance.borrow_mut().set(name, value.clone());
+                    Ok(value)
+                }
+                _ => Err(runtime_error(
+                    RuntimeErrorKind::UnsupportedExpression(Op::Equal.to_string()),
+                    *span,
+                )),
+            },
+            TokenTree::Cons(Op::Dot, operands, span)
+                if matches!(operands[0], TokenTree::Atom(Atom::Super(_), _)) =>
+            {
+                let [keyword, TokenTree::Atom(Atom::Ident(name, _), _)] = operands.as_slice()
+                else {
+                    unreachable!("`super` is always followed by a method name");
+                };
+                let distance = self
+                    .locals
+                    .depth(keyword)
+                    .expect("`super` is always resolved to the scope of its class");
+                // The scope binding `this` is always right inside the one binding `super`.
+                let (Some(Value::Class(superclass)), Some(Value::Instance(instance))) = (
+                    Environment::get_at(&self.environment, distance, "super"),
+                    Environment::get_at(&self.environment, distance - 1, "this"),
+                ) else {
+                    unreachable!("Methods of a subclass always bind `super` and `this`");
+                };
+                let method = superclass.find_method(name).ok_or_else(|| {
+                    runtime_error(RuntimeErrorKind::UndefinedProperty(name.to_string()), *span)
+                })?;
+                Ok(Value::Function(Rc::new(method.bind(instance))))
+            }
+            TokenTree::Cons(Op::Dot, operands, span) => {
+                let [object, TokenTree::Atom(Atom::Ident(name, _), _)] = operands.as_slice() else {
+                    unreachable!("Property accesses are always followed by a name");
+                };
+                let Value::Instance(instance) = self.evaluate(object)? else {
+                    return Err(runtime_error(
+                        RuntimeErrorKind::OnlyInstancesHaveProperties,
+                        *span,
+                    ));
+                };
+                LoxInstance::get(&instance, name).ok_or_else(|| {
+                    runtime_error(RuntimeErrorKind::UndefinedProperty(name.to_string()), *span)
+                })
+            }
+            TokenTree::Cons(op @ (Op::And | Op::Or), operands, _) => {
+                let lhs = self.evaluate(&operands[0])?;
+                // The left operand decides the result unless it is truthy for
+                // `and` or falsey for `or`.
+                if lhs.is_truthy() == matches!(op, Op::Or) {
+                    return Ok(lhs);
+                }
+                self.evaluate(&operands[1])
+            }
+            TokenTree::Cons(Op::Ternary, operands, _) => {
+                if self.evaluate(&operands[0])?.is_truthy() {
+                    self.evaluate(&operands[1])
+                } else {
+                    self.evaluate(&operands[2])
+                }
+            }
+            TokenTree::Cons(Op::Interpolate, operands, _) => {
+                let mut string = String::new();
+                for operand in operands {
+                    string += &self.evaluate(operand)?.to_string();
+                }
+                Ok(Value::String(string))
+            }
+            TokenTree::Cons(Op::Call, operands, span) => {
+                let (callee, arguments) = operands
+                    .split_first()
+                    .expect("Call expressions always have a callee");
+                let callee = self.evaluate(callee)?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(callee, arguments, *span)
+            }
+            TokenTree::Cons(op, operands, span) => match operands.as_slice() {
+                [rhs] => {
+                    let rhs = self.evaluate(rhs)?;
+                    Self::unary(*op, rhs, *span)
+                }
+                [lhs, rhs] => {
+                    let lhs = self.evaluate(lhs)?;
+                    let rhs = self.evaluate(rhs)?;
+                    Self::binary(*op, lhs, rhs, *span)
+                }
+                _ => Err(runtime_error(
+                    RuntimeErrorKind::UnsupportedExpression(op.to_string()),
+                    *span,
+                )),
+            },
+        }
+    }
+
+    fn look_up_variable(
+        &self,
+        name: &str,
+        expr: &TokenTree<'a>,
+        span: Span,
+    ) -> Result<Value<'a>, Error> {
+        let value = match self.locals.depth(expr) {
+            Some(distance) => Environment::get_at(&self.environment, distance, name),
+            None => self.globals.borrow().get(name),
+        };
+        value.ok_or_else(|| {
+            runtime_error(RuntimeErrorKind::UndefinedVariable(name.to_string()), span)
+        })
+    }
+
+    fn call(
+        &mut self,
+        callee: Value<'a>,
+        arguments: Vec<Value<'a>>,
+        span: Span,
+    ) -> Result<Value<'a>, Error> {
+        let Some(callable) = callee.as_callable() else {
+            return Err(runtime_error(RuntimeErrorKind::NotCallable, span));
+        };
+        if callable.arity() != arguments.len() {
+            return Err(runtime_error(
+                RuntimeErrorKind::ArityMismatch {
+                    expected: callable.arity(),
+                    found: arguments.len(),
+                },
+                span,
+            ));
+        }
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(runtime_error(RuntimeErrorKind::StackOverflow, span));
+        }
+        self.call_depth += 1;
+        let result = callable.call(self, arguments);
+        self.call_depth -= 1;
+        result
+    }
+
+    /// Executes a function `body` in `scope`, which already binds the arguments.
+    fn call_function(
+        &mut self,
+        body: &[Stmt<'a>],
+        scope: Environment<'a>,
+    ) -> Result<Value<'a>, Error> {
+        let flow = self.with_environment(Rc::new(RefCell::new(scope)), |interpreter| {
+            interpreter.execute_all(body)
+        })?;
+        Ok(match flow {
+            Flow::Break(value) => value,
+            Flow::Continue(()) => Value::Nil,
+        })
+    }
+
+    fn unary(op: Op, rhs: Value<'a>, span: Span) -> Result<Value<'a>, Error> {
+        match (op, rhs) {
+            (Op::Bang, rhs) => Ok(Value::Bool(!rhs.is_truthy())),
+            (Op::Minus, Value::Number(n)) => Ok(Value::Number(-n)),
+            (Op::Plus, Value::Number(n)) => Ok(Value::Number(n)),
+            (Op::Minus | Op::Plus, _) => {
+                Err(runtime_error(RuntimeErrorKind::OperandMustBeNumber, span))
+            }
+            (op, _) => Err(runtime_error(
+                RuntimeErrorKind::UnsupportedExpression(op.to_string()),
+                span,
+            )),
+        }
+    }
+
+    fn binary(op: Op, lhs: Value<'a>, rhs: Value<'a>, span: Span) -> Result<Value<'a>, Error> {
+        match (op, lhs, rhs) {
+            (Op::EqualEqual, lhs, rhs) => Ok(Value::Bool(lhs == rhs)),
+            (Op::BangEqual, lhs, rhs) => Ok(Value::Bool(lhs != rhs)),
+
+            (Op::Plus, Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
+            (Op::Plus, Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
+            (Op::Plus, _, _) => Err(runtime_error(
+                RuntimeErrorKind::OperandsMustBeNumbersOrStrings,
+                span,
+            )),
+
+            (Op::Minus, Value::Number(l), Value::Number(r)) => Ok(Value::Number(l - r)),
+            (Op::Star, Value::Number(l), Value::Number(r)) => Ok(Value::Number(l * r)),
+            (Op::Slash, Value::Number(l), Value::Number(r)) => Ok(Value::Number(l / r)),
+            (Op::Less, Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l < r)),
+            (Op::LessEqual, Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l <= r)),
+            (Op::Greater, Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l > r)),
+            (Op::GreaterEqual, Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l >= r)),
+            (
+                Op::Minus
+                | Op::Star
+                | Op::Slash
+                | Op::Less
+                | Op::LessEqual
+                | Op::Greater
+                | Op::GreaterEqual,
+                _,
+                _,
+            ) => Err(runtime_error(RuntimeErrorKind::OperandsMustBeNumbers, span)),
+
+            (op, _, _) => Err(runtime_error(
+                RuntimeErrorKind::UnsupportedExpression(op.to_string()),
+                span,
+            )),
+        }
+    }
+}
+
+fn runtime_error(kind: RuntimeErrorKind, span: Span) -> Error {
+    Error::RuntimeError(RuntimeError::new(kind, span))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{Lexer, Parser, Resolver};
+
+    use super::*;
+
+    /// An output sink that can still be read after the interpreter took ownership of it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(input: &str) -> Result<String, Error> {
+        let mut parser = Parser::with_lexer(Lexer::new(input));
+        let (program, errors) = parser.parse_program();
+        assert!(errors.is_empty(), "Failed to parse program: {:?}", errors);
+        let locals = Resolver::new()
+            .resolve(&program)
+            .expect("Failed to resolve program");
+        let output = SharedBuffer::default();
+        let mut interpreter = Interpreter::with_output(Box::new(output.clone()));
+        interpreter.add_locals(locals);
+        interpreter.interpret(&program)?;
+        let output = output.0.borrow();
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    fn evaluate(input: &str) -> Result<Value<'_>, Error> {
+        let mut parser = Parser::with_lexer(Lexer::new(input));
+        let tree = parser.parse().expect("Failed to parse expression");
+        Interpreter::new().evaluate(&tree)
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), Value::Number(7.0));
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), Value::Number(9.0));
+        assert_eq!(evaluate("-(8 / 2) - 1").unwrap(), Value::Number(-5.0));
+        assert_eq!(
+            evaluate("\"foo\" + \"bar\"").unwrap(),
+            Value::String("foobar".to_string())
+        );
+    }
+
+    #[test]
+    fn comparison_and_equality() {
+        assert_eq!(evaluate("1 < 2").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("2 >= 3").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("nil == nil").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("\"1\" == 1").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("!nil").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("!!0").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn runtime_errors() {
+        match evaluate("1 +\n-\"abc\"") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(e.kind(), RuntimeErrorKind::OperandMustBeNumber));
+                assert_eq!(e.line(), 2);
+            }
+            o => panic!("Expected a runtime error, got: {:?}", o),
+        }
+
+        match evaluate("\"a\" + 1") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(
+                    e.kind(),
+                    RuntimeErrorKind::OperandsMustBeNumbersOrStrings
+                ));
+            }
+            o => panic!("Expected a runtime error, got: {:?}", o),
+        }
+
+        match evaluate("true < false") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(e.kind(), RuntimeErrorKind::OperandsMustBeNumbers));
+            }
+            o => panic!("Expected a runtime error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn statements() {
+        assert_eq!(run("print 1 + 2; print \"a\";").unwrap(), "3\na\n");
+        assert_eq!(
+            run("if (1 < 2) print \"yes\"; else print \"no\";").unwrap(),
+            "yes\n"
+        );
+        assert_eq!(
+            run("{ print nil; { print !true; } }").unwrap(),
+            "nil\nfalse\n"
+        );
+        assert_eq!(
+            run("while (false) print 1; for (;false;) print 2;").unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn runtime_error_stops_execution() {
+        match run("print 1;\nprint -nil;\nprint 2;") {
+            Err(Error::RuntimeError(e)) => assert_eq!(e.line(), 2),
+            o => panic!("Expected a runtime error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn variables_and_scopes() {
+        assert_eq!(
+            run("var a = 1; var b; print a; print b; a = b = 2; print a + b;").unwrap(),
+            "1\nnil\n4\n"
+        );
+        assert_eq!(
+            run("var a = \"outer\"; { var a = \"inner\"; print a; a = \"x\"; } print a;").unwrap(),
+            "inner\nouter\n"
+        );
+        assert_eq!(
+            run("var sum = 0; for (var i = 0; i < 4; i = i + 1) sum = sum + i; print sum;")
+                .unwrap(),
+            "6\n"
+        );
+    }
+
+    #[test]
+    fn undefined_variable() {
+        match run("var a;\nprint b;") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(
+                    matches!(e.kind(), RuntimeErrorKind::UndefinedVariable(name) if name == "b")
+                );
+                assert_eq!(e.line(), 2);
+            }
+            o => panic!("Expected an undefined variable error, got: {:?}", o),
+        }
+        match run("{ var a = 1; }\na = 2;") {
+            Err(Error::RuntimeError(e)) => {
+                assert_eq!(e.kind().to_string(), "Undefined variable 'a'.");
+                assert_eq!(e.line(), 2);
+            }
+            o => panic!("Expected an undefined variable error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            run("fun add(a, b) { return a + b; } print add(1, 2); print add;").unwrap(),
+            "3\n<fn add>\n"
+        );
+        assert_eq!(
+            run(
+                "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } print fib(10);"
+            )
+            .unwrap(),
+            "55\n"
+        );
+        assert_eq!(
+            run("fun f() { while (true) { for (;;) { return \"out\"; } } } print f();").unwrap(),
+            "out\n"
+        );
+        assert_eq!(
+            run("fun f() {} print f(); print clock;").unwrap(),
+            "nil\n<native fn>\n"
+        );
+    }
+
+    #[test]
+    fn call_errors() {
+        match run("fun f(a) {}\nf(1, 2);") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(
+                    e.kind(),
+                    RuntimeErrorKind::ArityMismatch {
+                        expected: 1,
+                        found: 2
+                    }
+                ));
+                assert_eq!(e.line(), 2);
+            }
+            o => panic!("Expected an arity mismatch error, got: {:?}", o),
+        }
+        match run("\"not a function\"();") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(e.kind(), RuntimeErrorKind::NotCallable));
+            }
+            o => panic!("Expected a not callable error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn closures() {
+        let counter = "
+            fun makeCounter() {
+                var count = 0;
+                fun increment() {
+                    count = count + 1;
+                    return count;
+                }
+                return increment;
+            }
+            var a = makeCounter();
+            var b = makeCounter();
+            print a();
+            print a();
+            print b();
+        ";
+        assert_eq!(run(counter).unwrap(), "1\n2\n1\n");
+
+        let shared = "
+            var get;
+            var set;
+            {
+                var value = \"before\";
+                fun getter() { return value; }
+                fun setter(v) { value = v; }
+                get = getter;
+                set = setter;
+            }
+            set(\"after\");
+            print get();
+        ";
+        assert_eq!(run(shared).unwrap(), "after\n");
+    }
+
+    #[test]
+    fn classes() {
+        let program = "
+            class Counter {
+                init(start) {
+                    this.count = start;
+                }
+                increment() {
+                    this.count = this.count + 1;
+                    return this;
+                }
+            }
+            var counter = Counter(10);
+            counter.increment().increment();
+            print counter.count;
+            print Counter;
+            print counter;
+            var increment = counter.increment;
+            increment();
+            print counter.count;
+        ";
+        assert_eq!(run(program).unwrap(), "12\nCounter\nCounter instance\n13\n");
+    }
+
+    #[test]
+    fn initializers() {
+        let program = "
+            class Foo {
+                init() {
+                    this.value = 1;
+                    return;
+                    this.value = 2;
+                }
+            }
+            var foo = Foo();
+            print foo.value;
+            print foo.init();
+            print foo.init() == foo;
+        ";
+        assert_eq!(run(program).unwrap(), "1\nFoo instance\ntrue\n");
+
+        match run("class Foo { init(a) {} }\nFoo();") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(
+                    e.kind(),
+                    RuntimeErrorKind::ArityMismatch {
+                        expected: 1,
+                        found: 0
+                    }
+                ));
+            }
+            o => panic!("Expected an arity mismatch error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn property_errors() {
+        match run("class Foo {}\nprint Foo().bar;") {
+            Err(Error::RuntimeError(e)) => {
+                assert_eq!(e.kind().to_string(), "Undefined property 'bar'.");
+                assert_eq!(e.line(), 2);
+            }
+            o => panic!("Expected an undefined property error, got: {:?}", o),
+        }
+        match run("var a = 1; print a.b;") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(
+                    e.kind(),
+                    RuntimeErrorKind::OnlyInstancesHaveProperties
+                ));
+            }
+            o => panic!("Expected an error for a property on a number, got: {:?}", o),
+        }
+        match run("var a = \"str\"; a.b = 1;") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(
+                    e.kind(),
+                    RuntimeErrorKind::OnlyInstancesHaveFields
+                ));
+            }
+            o => panic!("Expected an error for a field on a string, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn inheritance() {
+        let program = "
+            class A {
+                init(name) { this.name = name; }
+                greet() { return \"A \" + this.name; }
+                shout() { return this.greet() + \"!\"; }
+            }
+            class B < A {
+                greet() { return \"B then \" + super.greet(); }
+            }
+            class C < B {}
+            var c = C(\"c\");
+            print c.greet();
+            print c.shout();
+        ";
+        assert_eq!(run(program).unwrap(), "B then A c\nB then A c!\n");
+
+        match run("var NotAClass = 1;\nclass A < NotAClass {}") {
+            Err(Error::RuntimeError(e)) => {
+                assert!(matches!(e.kind(), RuntimeErrorKind::SuperclassMustBeClass));
+                assert_eq!(e.line(), 2);
+            }
+            o => panic!("Expected a superclass error, got: {:?}", o),
+        }
+        match run("class A {} class B < A { f() { return super.missing; } } B().f();") {
+            Err(Error::RuntimeError(e)) => {
+                assert_eq!(e.kind().to_string(), "Undefined property 'missing'.");
+            }
+            o => panic!("Expected an undefined property error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn resolved_closures() {
+        let program = "
+            var a = \"global\";
+            {
+                fun showA() {
+                    print a;
+                }
+                showA();
+                var a = \"block\";
+                showA();
+            }
+        ";
+        assert_eq!(run(program).unwrap(), "global\nglobal\n");
+    }
+
+    #[test]
+    fn logical_operators() {
+        assert_eq!(
+            run("print nil or \"yes\"; print 0 and \"also\"; print false and 1; print 1 or 2;")
+                .unwrap(),
+            "yes\nalso\nfalse\n1\n"
+        );
+        assert_eq!(
+            run("var a = 0; fun bump() { a = a + 1; return true; } false and bump(); true or bump(); print a;")
+                .unwrap(),
+            "0\n"
+        );
+    }
+
+    #[test]
+    fn ternary() {
+        assert_eq!(
+            run(
+                "var x; print x == nil ? \"default\" : x; x = 2; print x == nil ? \"default\" : x;"
+            )
+            .unwrap(),
+            "default\n2\n"
+        );
+        assert_eq!(
+            run("print false ? 1 : nil ? 2 : 3; true ? nil : -\"never evaluated\";").unwrap(),
+            "3\n"
+        );
+    }
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(
+            run(r#"var name = "World"; print "Hello ${name}!"; print "${1 + 1}${nil} ${"a${true}"}";"#)
+                .unwrap(),
+            "Hello World!\n2nil atrue\n"
+        );
+        assert_eq!(
+            run(r#"fun f() {} class A {} print "${f} ${A} ${A()} \${x}";"#).unwrap(),
+            "<fn f> A A instance ${x}\n"
+        );
+    }
+
+    #[test]
+    fn stack_overflow() {
+        // The test threads are too small to reach the limit.
+        let test = || {
+            assert_eq!(
+                run("fun count(n) { if (n == 0) return 0; return 1 + count(n - 1); } print count(999);")
+                    .unwrap(),
+                "999\n"
+            );
+            match run("fun f(n) {\n  return f(n + 1);\n}\nf(0);") {
+                Err(Error::RuntimeError(e)) => {
+                    assert!(matches!(e.kind(), RuntimeErrorKind::StackOverflow));
+                    assert_eq!(e.line(), 2);
+                }
+                o => panic!("Expected a stack overflow, got: {:?}", o),
+            }
+        };
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        // The second `café` spells the accent with a combining character.
+        assert_eq!(
+            run("var caf\u{e9} = 1; var 変数 = 2; print cafe\u{301} + 変数;").unwrap(),
+            "3\n"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::{LoxCallable, LoxClass, LoxFunction, LoxInstance, NativeFunction};
+
+#[derive(Debug, Clone)]
+pub enum Value<'a> {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Function(Rc<LoxFunction<'a>>),
+    NativeFunction(Rc<NativeFunction>),
+    Class(Rc<LoxClass<'a>>),
+    Instance(Rc<RefCell<LoxInstance<'a>>>),
+}
+
+impl<'a> Value<'a> {
+    /// `nil` and `false` are falsey, everything else is truthy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    pub fn as_callable(&self) -> Option<&dyn LoxCallable<'a>> {
+        match self {
+            Value::Function(function) => Some(function.as_ref()),
+            Value::NativeFunction(function) => Some(function.as_ref()),
+            Value::Class(class) => Some(class),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Function(l), Value::Function(r)) => Rc::ptr_eq(l, r),
+            (Value::NativeFunction(l), Value::NativeFunction(r)) => Rc::ptr_eq(l, r),
+            (Value::Class(l), Value::Class(r)) => Rc::ptr_eq(l, r),
+            (Value::Instance(l), Value::Instance(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Function(function) => write!(f, "<fn {}>", function.name()),
+            Value::NativeFunction(_) => write!(f, "<native fn>"),
+            Value::Class(class) => write!(f, "{}", class.name()),
+            Value::Instance(instance) => write!(f, "{} instance", instance.borrow().class().name()),
+        }
+    }
+}
@@ -1,12 +1,25 @@
+use std::{collections::VecDeque, rc::Rc};
+
+use unicode_ident::{is_xid_continue, is_xid_start};
+
 use crate::{
     error::{Error, LexingError, LexingErrorKind},
-    token::{Token, TokenType},
+    source_map::SourceMap,
+    token::{Literal, Span, Token, TokenType, decode_escape},
 };
 
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
     source_code: &'a str,
     byte_offset: usize,
+    source_map: Rc<SourceMap<'a>>,
+    /// The start of the string around each `${` expression being lexed and
+    /// the number of unclosed braces inside the expression, innermost last.
+    interpolations: Vec<(usize, usize)>,
+    /// Whether comments are returned as tokens rather than skipped.
+    comments: bool,
+    /// Tokens lexed ahead of the current position by `peek` and `peek_nth`.
+    lookahead: VecDeque<Result<Token<'a>, Error>>,
 }
 
 impl<'a> Lexer<'a> {
@@ -14,44 +27,200 @@ impl<'a> Lexer<'a> {
         Self {
             source_code: stream,
             byte_offset: 0,
+            source_map: Rc::new(SourceMap::new(stream)),
+            interpolations: Vec::new(),
+            comments: false,
+            lookahead: VecDeque::new(),
         }
     }
 
-    pub fn line(&self) -> usize {
-        self.source_code[..self.byte_offset].lines().count()
+    /// Returns comments as [`TokenType::Comment`] tokens, for tools that need
+    /// to keep them such as the formatter. The parser doesn't accept them.
+    pub fn with_comments(mut self) -> Self {
+        self.comments = true;
+        self
     }
 
-    pub fn expect(&mut self, expected: TokenType) -> Result<Token<'a>, Error> {
-        match self.next() {
-            Some(Ok(token)) if token.ty() == expected => Ok(token),
-            Some(Ok(token)) => Err(Error::LexingError(LexingError::with_line(
-                LexingErrorKind::UnexpectedToken {
-                    expected,
-                    found: token.ty(),
-                },
-                self.line(),
+    pub fn source_map(&self) -> &SourceMap<'a> {
+        &self.source_map
+    }
+
+    /// The empty span right after the last character of the source code.
+    pub fn end_of_input(&self) -> Span {
+        let end = self.source_code.len();
+        self.span(end, end)
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        self.source_map.span(start, end)
+    }
+
+    /// Skips a block comment starting at `start`, including any comments
+    /// nested inside it. The opening `/` has already been consumed.
+    fn block_comment(&mut self, start: usize) -> Result<(), Error> {
+        let mut depth = 0;
+        let mut offset = start;
+        while offset < self.source_code.len() {
+            let rest = &self.source_code[offset..];
+            if rest.starts_with("/*") {
+                depth += 1;
+                offset += 2;
+            } else if rest.starts_with("*/") {
+                depth -= 1;
+                offset += 2;
+                if depth == 0 {
+                    self.byte_offset = offset;
+                    return Ok(());
+                }
+            } else {
+                offset += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+
+        self.byte_offset = self.source_code.len();
+        let span = self.span(start, self.byte_offset);
+        Err(Error::LexingError(LexingError::new(
+            LexingErrorKind::UnterminatedComment { line: span.line() },
+            span,
+        )))
+    }
+
+    /// The comment from `start` up to the current position.
+    fn comment(&self, start: usize) -> Token<'a> {
+        let lexeme = &self.source_code[start..self.byte_offset];
+        Token::new(
+            TokenType::Comment,
+            lexeme,
+            self.span(start, self.byte_offset),
+        )
+    }
+
+    /// Lexes the rest of the number starting at `start`, whose first digit has
+    /// already been consumed.
+    fn number(&mut self, start: usize) -> Result<Token<'a>, Error> {
+        let rest = &self.source_code[start..];
+        let digits = |s: &str, radix: u32| {
+            s.find(|c: char| !(c.is_digit(radix) || c == '_'))
+                .unwrap_or(s.len())
+        };
+
+        let len = if let Some("0x" | "0X" | "0b" | "0B" | "0o" | "0O") = rest.get(..2) {
+            // Take any letters too, so `0xFG` is one invalid number.
+            2 + digits(&rest[2..], 36)
+        } else {
+            let mut len = digits(rest, 10);
+            // A dot only belongs to the number if a digit follows it, so
+            // `123.` is a number and a dot.
+            if rest[len..].starts_with('.')
+                && rest[len + 1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                len += 1 + digits(&rest[len + 1..], 10);
+            }
+            if rest[len..].starts_with(['e', 'E']) {
+                len += 1;
+                if rest[len..].starts_with(['+', '-']) {
+                    len += 1;
+                }
+                len += digits(&rest[len..], 10);
+            }
+            len
+        };
+
+        self.byte_offset = start + len;
+        let lexeme = &rest[..len];
+        let span = self.span(start, self.byte_offset);
+        match Literal::try_from(lexeme) {
+            Ok(literal) => Ok(Token::new(TokenType::Literal(literal), lexeme, span)),
+            Err(_) => Err(Error::LexingError(LexingError::new(
+                LexingErrorKind::InvalidNumber(lexeme.to_string()),
+                span,
             ))),
-            Some(Err(e)) => Err(e),
-            None => Err(Error::UnexpectedEndOfInput),
         }
     }
 
-    pub fn peek(&self) -> Option<Result<Token<'a>, Error>> {
-        let mut lexer_clone = self.clone();
-        lexer_clone.next()
+    /// Lexes the rest of the string, or the part of one after an embedded
+    /// expression, starting at `start`. The opening `"` or `}` has already
+    /// been consumed. The whole string starts at `string_start`.
+    fn string(&mut self, start: usize, string_start: usize) -> Result<Token<'a>, Error> {
+        let body = &self.source_code[self.byte_offset..];
+        let mut end = None;
+        let mut invalid_escape = None;
+        let mut i = 0;
+        while let Some(c) = body[i..].chars().next() {
+            match c {
+                '"' => {
+                    end = Some((i, Literal::String, 1));
+                    break;
+                }
+                '$' if body[i + 1..].starts_with('{') => {
+                    end = Some((i, Literal::Interpolation, 2));
+                    break;
+                }
+                '\\' => {
+                    let len = match decode_escape(&body[i + 1..]) {
+                        Some((_, len)) => len,
+                        None => {
+                            // Skip the character after the backslash so an
+                            // escaped quote doesn't end the string.
+                            let len = body[i + 1..].chars().next().map_or(0, char::len_utf8);
+                            invalid_escape.get_or_insert(i..i + 1 + len);
+                            len
+                        }
+                    };
+                    i += 1 + len;
+                }
+                c => i += c.len_utf8(),
+            }
+        }
+
+        let Some((end, literal, delimiter)) = end else {
+            self.byte_offset = self.source_code.len();
+            return Err(Error::LexingError(LexingError::new(
+                LexingErrorKind::UnterminatedString,
+                self.span(start, self.byte_offset),
+            )));
+        };
+
+        let body_offset = self.byte_offset;
+        self.byte_offset += end + delimiter;
+        if literal == Literal::Interpolation {
+            self.interpolations.push((string_start, 0));
+        }
+        if let Some(escape) = invalid_escape {
+            return Err(Error::LexingError(LexingError::new(
+                LexingErrorKind::InvalidEscape(body[escape.clone()].to_string()),
+                self.span(body_offset + escape.start, body_offset + escape.end),
+            )));
+        }
+
+        let lexeme = &self.source_code[start..self.byte_offset];
+        let span = self.span(start, self.byte_offset);
+        Ok(Token::new(TokenType::Literal(literal), lexeme, span))
     }
-}
 
-impl<'a> Iterator for Lexer<'a> {
-    type Item = Result<Token<'a>, Error>;
+    /// Returns the next token without consuming it.
+    pub fn peek(&mut self) -> Option<&Result<Token<'a>, Error>> {
+        self.peek_nth(0)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Returns the token `n` places after the next one without consuming
+    /// anything, so `peek_nth(0)` is the same as `peek()`.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Result<Token<'a>, Error>> {
+        while self.lookahead.len() <= n {
+            let token = self.lex()?;
+            self.lookahead.push_back(token);
+        }
+        self.lookahead.get(n)
+    }
+
+    /// Lexes the token at the current position.
+    fn lex(&mut self) -> Option<Result<Token<'a>, Error>> {
         let mut iterator = self.source_code[self.byte_offset..].chars().peekable();
 
         let is_punct = |lexeme: char| -> bool {
             matches!(
                 lexeme,
-                '(' | ')' | '{' | '}' | ',' | '.' | ';' | '+' | '-' | '*'
+                '(' | ')' | '{' | '}' | ',' | '.' | ';' | '+' | '-' | '*' | '?' | ':'
             )
         };
 
@@ -61,82 +230,105 @@ impl<'a> Iterator for Lexer<'a> {
 
             match c {
                 c if c.is_whitespace() => continue,
-                c if is_punct(c) => {}
+                '}' if matches!(self.interpolations.last(), Some((_, 0))) => {
+                    // The end of an expression embedded in a string.
+                    let (string_start, _) = self.interpolations.pop().unwrap();
+                    return Some(self.string(cur_byte_offset, string_start));
+                }
+                c if is_punct(c) => {
+                    if let Some((_, depth)) = self.interpolations.last_mut() {
+                        match c {
+                            '{' => *depth += 1,
+                            '}' => *depth -= 1,
+                            _ => {}
+                        }
+                    }
+                }
 
                 '!' | '=' | '<' | '>' => {
                     self.byte_offset += match iterator.peek() {
-                        Some(&next) if next == '=' => next.len_utf8(),
+                        Some('=') => '='.len_utf8(),
                         _ => 0,
                     };
                 }
 
                 '/' => match iterator.peek() {
-                    Some(&next) if next == '/' => {
-                        let newline = iterator.position(|c| c == '\n');
-                        match newline {
-                            Some(pos) => self.byte_offset += pos + 1,
-                            None => return None,
+                    Some('/') => {
+                        // The comment doesn't include the line ending.
+                        self.byte_offset += self.source_code[self.byte_offset..]
+                            .find(['\r', '\n'])
+                            .unwrap_or(self.source_code.len() - self.byte_offset);
+                        if self.comments {
+                            return Some(Ok(self.comment(cur_byte_offset)));
                         }
+                        iterator = self.source_code[self.byte_offset..].chars().peekable();
                         continue; // Skip to the next iteration
                     }
+                    Some('*') => {
+                        if let Err(e) = self.block_comment(cur_byte_offset) {
+                            return Some(Err(e));
+                        }
+                        if self.comments {
+                            return Some(Ok(self.comment(cur_byte_offset)));
+                        }
+                        iterator = self.source_code[self.byte_offset..].chars().peekable();
+                        continue;
+                    }
                     _ => {}
                 },
 
                 // Literals
-                c if c.is_ascii_alphabetic() || c == '_' => {
-                    let is_lit = |next: char| -> bool {
-                        next.is_ascii_alphabetic() || next.is_ascii_digit() || matches!(next, '_')
-                    };
-                    let len = iterator.take_while(|&next| is_lit(next)).count();
+                c if is_xid_start(c) || c == '_' => {
+                    let len = iterator
+                        .take_while(|&next| is_xid_continue(next))
+                        .map(char::len_utf8)
+                        .sum::<usize>();
                     self.byte_offset += len;
                 }
 
-                '"' => {
-                    if let Some(end) = iterator.position(|c| c == '"') {
-                        self.byte_offset += end + 1;
-                    } else {
-                        self.byte_offset = self.source_code.len();
-                        return Some(Err(Error::LexingError(LexingError::with_line(
-                            LexingErrorKind::UnterminatedString,
-                            self.line(),
-                        ))));
-                    }
-                }
+                '"' => return Some(self.string(cur_byte_offset, cur_byte_offset)),
 
-                c if c.is_ascii_digit() => {
-                    let len = iterator
-                        .take_while(|&next| next.is_ascii_digit() || next == '.')
-                        .count()
-                        + 1;
-
-                    let mut split =
-                        self.source_code[cur_byte_offset..cur_byte_offset + len].splitn(3, '.');
-                    self.byte_offset += match (split.next(), split.next(), split.next()) {
-                        (Some(one), Some(two), Some(_)) => one.len() + two.len(),
-                        (Some(one), Some(two), None) if two.is_empty() => one.len() - 1,
-                        _ => len - 1,
-                    };
-                }
+                c if c.is_ascii_digit() => return Some(self.number(cur_byte_offset)),
 
                 _ => {
-                    return Some(Err(Error::LexingError(LexingError::with_line(
+                    return Some(Err(Error::LexingError(LexingError::new(
                         LexingErrorKind::UnexpectedCharacter(c),
-                        self.line(),
+                        self.span(cur_byte_offset, self.byte_offset),
                     ))));
                 }
             };
 
             let lexeme = &self.source_code[cur_byte_offset..self.byte_offset];
             let token_ty = TokenType::from(lexeme);
-            return Some(Ok(Token::new(token_ty, lexeme)));
+            let span = self.span(cur_byte_offset, self.byte_offset);
+            return Some(Ok(Token::new(token_ty, lexeme, span)));
+        }
+
+        // The input ended inside an expression embedded in a string.
+        if let Some(&(start, _)) = self.interpolations.first() {
+            self.interpolations.clear();
+            return Some(Err(Error::LexingError(LexingError::new(
+                LexingErrorKind::UnterminatedString,
+                self.span(start, self.source_code.len()),
+            ))));
         }
         None
     }
 }
 
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lookahead.pop_front().or_else(|| self.lex())
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::token::{Literal, Operator, UnaryOperator};
+    use std::borrow::Cow;
+
+    use crate::token::{Operator, UnaryOperator};
 
     use super::*;
 
@@ -154,7 +346,7 @@ mod test {
         match lexer.next() {
             Some(Err(Error::LexingError(e))) => {
                 assert!(matches!(e.kind(), LexingErrorKind::UnexpectedCharacter(_)));
-                assert!(matches!(e.line(), Some(1)));
+                assert_eq!(e.line(), 1);
             }
             o => panic!("Expected an error for unexpected character, got: {:?}", o),
         }
@@ -163,7 +355,8 @@ mod test {
     #[test]
     fn identifiers() {
         let input = "andy formless fo _ _123 _abc ab123
-        abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890_";
+        abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890_
+        café 変数 Ωmega _ñ x\u{301} ∂x";
         let mut lexer = Lexer::new(input);
 
         let expected = vec![
@@ -175,6 +368,11 @@ mod test {
             "_abc",
             "ab123",
             "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890_",
+            "café",
+            "変数",
+            "Ωmega",
+            "_ñ",
+            "x\u{301}",
         ];
 
         for expected_lexeme in expected {
@@ -190,6 +388,26 @@ mod test {
                 None => panic!("Expected more tokens, but got None"),
             }
         }
+
+        // `∂` is a math symbol rather than a letter.
+        match lexer.next() {
+            Some(Err(Error::LexingError(e))) => {
+                assert!(matches!(
+                    e.kind(),
+                    LexingErrorKind::UnexpectedCharacter('∂')
+                ));
+            }
+            o => panic!("Expected an error for a symbol, got: {:?}", o),
+        }
+        assert!(matches!(lexer.next(), Some(Ok(token)) if token.lexeme() == "x"));
+
+        // A precomposed `é` and an `e` followed by a combining accent are the
+        // same identifier once normalized.
+        assert_eq!(
+            Token::normalize("caf\u{e9}"),
+            Token::normalize("cafe\u{301}")
+        );
+        assert!(matches!(Token::normalize("café"), Cow::Borrowed(_)));
     }
 
     #[test]
@@ -235,12 +453,161 @@ mod test {
         match lexer.next() {
             Some(Err(Error::LexingError(e))) => {
                 assert!(matches!(e.kind(), LexingErrorKind::UnterminatedString));
-                assert!(matches!(e.line(), Some(1)));
+                assert_eq!(e.line(), 1);
             }
             o => panic!("Expected an error for unterminated string, got: {:?}", o),
         }
     }
 
+    #[test]
+    fn escapes() {
+        let input = r#""a\nb" "say \"hi\"" "\t\r\\\0" "\u{48}\u{1F600}" "plain""#;
+        let expected = ["a\nb", "say \"hi\"", "\t\r\\\0", "H\u{1F600}", "plain"];
+
+        let tokens = Lexer::new(input)
+            .map(|token| token.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens.len(), expected.len());
+        for (token, expected) in tokens.iter().zip(expected) {
+            assert!(matches!(token.ty(), TokenType::Literal(Literal::String)));
+            assert_eq!(Token::unescape(token.lexeme()), expected);
+        }
+
+        assert!(matches!(
+            Token::unescape(r#""plain""#),
+            Cow::Borrowed("plain")
+        ));
+        assert!(matches!(Token::unescape(r#""a\nb""#), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        let input = r#""bad \q escape" "\u{}" "\u{110000}" "\" after"#;
+        let mut lexer = Lexer::new(input);
+
+        for (escape, start) in [(r"\q", 5), (r"\u", 17), (r"\u", 24)] {
+            match lexer.next() {
+                Some(Err(Error::LexingError(e))) => {
+                    assert!(matches!(e.kind(), LexingErrorKind::InvalidEscape(s) if s == escape));
+                    assert_eq!(e.span().start(), start);
+                }
+                o => panic!("Expected an invalid escape error, got: {:?}", o),
+            }
+        }
+
+        match lexer.next() {
+            Some(Err(Error::LexingError(e))) => {
+                assert!(matches!(e.kind(), LexingErrorKind::UnterminatedString));
+            }
+            o => panic!("Expected an unterminated string error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn interpolation() {
+        let input = r#""a ${ {b} } c ${ "d${e}" }" }"#;
+        let expected = vec![
+            (r#""a ${"#, TokenType::Literal(Literal::Interpolation)),
+            (
+                "{",
+                TokenType::Operator(Operator::Unary(UnaryOperator::LeftBrace)),
+            ),
+            ("b", TokenType::Literal(Literal::Identifier)),
+            (
+                "}",
+                TokenType::Operator(Operator::Unary(UnaryOperator::RightBrace)),
+            ),
+            (r#"} c ${"#, TokenType::Literal(Literal::Interpolation)),
+            (r#""d${"#, TokenType::Literal(Literal::Interpolation)),
+            ("e", TokenType::Literal(Literal::Identifier)),
+            (r#"}""#, TokenType::Literal(Literal::String)),
+            (r#"}""#, TokenType::Literal(Literal::String)),
+            (
+                "}",
+                TokenType::Operator(Operator::Unary(UnaryOperator::RightBrace)),
+            ),
+        ];
+
+        let tokens = Lexer::new(input)
+            .map(|token| token.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens.len(), expected.len());
+        for (token, (lexeme, ty)) in tokens.iter().zip(expected) {
+            assert_eq!(token.lexeme(), lexeme);
+            assert_eq!(token.ty(), ty);
+        }
+
+        assert_eq!(Token::unescape(r#""a ${"#), "a ");
+        assert_eq!(Token::unescape(r#"} c ${"#), " c ");
+        assert_eq!(Token::unescape(r#"}\${""#), "${");
+    }
+
+    #[test]
+    fn unterminated_interpolation() {
+        for input in [r#"print "a ${ b "#, r#"print "a ${ b } c ${ "d ${ e"#] {
+            let mut lexer = Lexer::new(input);
+            let error = lexer.find_map(Result::err);
+            match error {
+                Some(Error::LexingError(e)) => {
+                    assert!(matches!(e.kind(), LexingErrorKind::UnterminatedString));
+                    assert_eq!(e.span().start(), 6);
+                    assert_eq!(e.span().end(), input.len());
+                }
+                o => panic!("Expected an unterminated string error, got: {:?}", o),
+            }
+            assert!(lexer.next().is_none());
+        }
+    }
+
+    #[test]
+    fn comments() {
+        let input = "a // café\nb /* one\n/* two */ still\n*/ c /**/ d\n/* /* */ e";
+        let mut lexer = Lexer::new(input);
+
+        for (lexeme, line) in [("a", 1), ("b", 2), ("c", 4), ("d", 4)] {
+            match lexer.next() {
+                Some(Ok(token)) => {
+                    assert_eq!(token.lexeme(), lexeme);
+                    assert_eq!(token.span().line(), line);
+                }
+                o => panic!("Expected {lexeme}, got: {:?}", o),
+            }
+        }
+
+        match lexer.next() {
+            Some(Err(Error::LexingError(e))) => {
+                assert!(matches!(
+                    e.kind(),
+                    LexingErrorKind::UnterminatedComment { line: 5 }
+                ));
+                assert_eq!(e.span().column(), 1);
+            }
+            o => panic!("Expected an unterminated comment error, got: {:?}", o),
+        }
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn comment_tokens() {
+        let input = "a // one\r\n/* two\n */ b //";
+        let tokens = Lexer::new(input)
+            .with_comments()
+            .map(|token| token.map(|token| (token.ty(), token.lexeme())))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let ident = TokenType::Literal(Literal::Identifier);
+        assert_eq!(
+            tokens,
+            [
+                (ident, "a"),
+                (TokenType::Comment, "// one"),
+                (TokenType::Comment, "/* two\n */"),
+                (ident, "b"),
+                (TokenType::Comment, "//"),
+            ]
+        );
+    }
+
     #[test]
     fn numbers() {
         let input = "123 123.456 .456 123.";
@@ -266,14 +633,65 @@ mod test {
         }
     }
 
+    #[test]
+    fn extended_numbers() {
+        let input = "0xFF 0XfF 0b1010 0o755 1e-9 6.02E23 1_000_000 0x1_0 1.5e+3 0 2.";
+        let expected = [
+            255.0,
+            255.0,
+            10.0,
+            493.0,
+            1e-9,
+            6.02e23,
+            1_000_000.0,
+            16.0,
+            1500.0,
+            0.0,
+            2.0,
+        ];
+
+        let mut lexer = Lexer::new(input);
+        for expected in expected {
+            match lexer.next() {
+                Some(Ok(token)) => {
+                    assert_eq!(token.ty(), TokenType::Literal(Literal::Number(expected)));
+                }
+                o => panic!("Expected the number {expected}, got: {:?}", o),
+            }
+        }
+        assert!(matches!(lexer.next(), Some(Ok(token)) if token.lexeme() == "."));
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        let input = "0x 1e 0b102 0xFG 1__ 1e+ 0o8 1_.5 0x_1 0xFFFFFFFFFFFFFFFFFF";
+        let lexer = Lexer::new(input);
+
+        let expected = input.split(' ').collect::<Vec<_>>();
+        let errors = lexer.collect::<Vec<_>>();
+        assert_eq!(errors.len(), expected.len());
+        for (error, expected) in errors.into_iter().zip(expected) {
+            match error {
+                Err(Error::LexingError(e)) => {
+                    assert!(
+                        matches!(e.kind(), LexingErrorKind::InvalidNumber(n) if n == expected),
+                        "Expected {expected} to be invalid, got: {:?}",
+                        e
+                    );
+                }
+                o => panic!("Expected an invalid number error, got: {:?}", o),
+            }
+        }
+    }
+
     #[test]
     fn punctuators() {
-        let input = r#"(){};,+-*!===<=>=!=<>/."#;
+        let input = r#"(){};,+-*!===<=>=!=<>/.?:"#;
         let mut lexer = Lexer::new(input);
 
         let expected_tokens = vec![
             "(", ")", "{", "}", ";", ",", "+", "-", "*", "!=", "==", "<=", ">=", "!=", "<", ">",
-            "/", ".",
+            "/", ".", "?", ":",
         ];
 
         for expected_token in expected_tokens {
@@ -288,6 +706,34 @@ mod test {
         }
     }
 
+    #[test]
+    fn spans() {
+        let input = "var a = \"é\nb\";\n  print a;";
+        let lexer = Lexer::new(input);
+
+        let expected = vec![
+            ("var", 0, 3, 1, 1),
+            ("a", 4, 5, 1, 5),
+            ("=", 6, 7, 1, 7),
+            ("\"é\nb\"", 8, 14, 1, 9),
+            (";", 14, 15, 2, 3),
+            ("print", 18, 23, 3, 3),
+            ("a", 24, 25, 3, 9),
+            (";", 25, 26, 3, 10),
+        ];
+
+        let tokens = lexer.map(|token| token.unwrap()).collect::<Vec<_>>();
+        assert_eq!(tokens.len(), expected.len());
+        for (token, (lexeme, start, end, line, column)) in tokens.iter().zip(expected) {
+            assert_eq!(token.lexeme(), lexeme);
+            assert_eq!(token.span(), Span::new(start, end, line, column));
+        }
+
+        let mut lexer = Lexer::new("a\n");
+        lexer.next();
+        assert_eq!(lexer.end_of_input(), Span::new(2, 2, 2, 1));
+    }
+
     #[test]
     fn whitespace() {
         let input = "space      tabs\t\t\t\tnewlines\n\n\n\n\nend";
@@ -308,4 +754,51 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn lookahead() {
+        let mut lexer = Lexer::new("a + @ b");
+        let lexeme = |token: Option<&Result<Token, Error>>| match token {
+            Some(Ok(token)) => token.lexeme().to_string(),
+            o => panic!("Expected a token, got: {:?}", o),
+        };
+
+        assert_eq!(lexeme(lexer.peek()), "a");
+        assert_eq!(lexeme(lexer.peek_nth(1)), "+");
+        assert!(matches!(lexer.peek_nth(2), Some(Err(_))));
+        assert_eq!(lexeme(lexer.peek_nth(3)), "b");
+        assert!(lexer.peek_nth(4).is_none());
+        // Looking ahead doesn't consume anything.
+        assert_eq!(lexeme(lexer.peek()), "a");
+
+        assert_eq!(lexeme(lexer.next().as_ref()), "a");
+        assert_eq!(lexeme(lexer.peek()), "+");
+        assert_eq!(lexeme(lexer.next().as_ref()), "+");
+        assert!(matches!(lexer.peek(), Some(Err(_))));
+        assert!(matches!(lexer.next(), Some(Err(_))));
+        assert_eq!(lexeme(lexer.peek()), "b");
+        assert_eq!(lexeme(lexer.next().as_ref()), "b");
+        assert!(lexer.peek().is_none());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn json() {
+        let tokens = Lexer::new(r#"x "a\t${0x1F}""#)
+            .map(|token| serde_json::to_value(token.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens[0],
+            serde_json::json!({
+                "type": "IDENTIFIER",
+                "lexeme": "x",
+                "value": null,
+                "span": {"start": 0, "end": 1, "line": 1, "column": 1},
+            })
+        );
+        assert_eq!(tokens[1]["type"], "INTERPOLATION");
+        assert_eq!(tokens[1]["value"], "a\t");
+        assert_eq!(tokens[2]["type"], "NUMBER");
+        assert_eq!(tokens[2]["value"], 31.0);
+    }
 }
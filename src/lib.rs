@@ -1,7 +1,16 @@
+pub mod diagnostics;
 pub mod error;
+pub mod formatter;
+pub mod interpreter;
 mod lexer;
+pub mod lint;
 mod parser;
+pub mod repl;
+mod resolver;
+mod source_map;
 pub mod token;
 
 pub use lexer::Lexer;
 pub use parser::Parser;
+pub use resolver::{Locals, Resolver};
+pub use source_map::SourceMap;
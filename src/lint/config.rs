@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::diagnostics::Severity;
+
+/// How a rule reports what it finds, as set in `lox.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Off,
+    Note,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// The severity to report with, or `None` if the rule is off.
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            Level::Off => None,
+            Level::Note => Some(Severity::Note),
+            Level::Warning => Some(Severity::Warning),
+            Level::Error => Some(Severity::Error),
+        }
+    }
+}
+
+impl From<Severity> for Level {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Note => Level::Note,
+            Severity::Warning => Level::Warning,
+            Severity::Error => Level::Error,
+        }
+    }
+}
+
+/// The settings read from a `lox.toml` file, such as:
+///
+/// ```toml
+/// [lint]
+/// unused-parameter = "off"
+/// shadowing = "error"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// The level of each rule that doesn't use its default one.
+    #[serde(default)]
+    lint: HashMap<String, Level>,
+}
+
+impl Config {
+    pub const FILE_NAME: &str = "lox.toml";
+
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        toml::from_str(source).map_err(ConfigError::Invalid)
+    }
+
+    /// Reads the `lox.toml` in `dir` or the closest of its ancestors that
+    /// has one, falling back to the default settings.
+    pub fn discover(dir: &Path) -> Result<Self, ConfigError> {
+        let Some(path) = dir
+            .ancestors()
+            .map(|dir| dir.join(Self::FILE_NAME))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Self::default());
+        };
+        let source =
+            std::fs::read_to_string(&path).map_err(|e| ConfigError::Read(path.clone(), e))?;
+        Self::from_toml(&source)
+    }
+
+    /// The level set for the rule `id`, if any.
+    pub fn level(&self, id: &str) -> Option<Level> {
+        self.lint.get(id).copied()
+    }
+
+    /// The IDs of every rule with a level set.
+    pub fn rule_ids(&self) -> impl Iterator<Item = &str> {
+        self.lint.keys().map(String::as_str)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Invalid(toml::de::Error),
+    UnknownRule(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "Failed to read {}: {e}", path.display()),
+            ConfigError::Invalid(e) => write!(f, "Invalid {}: {e}", Config::FILE_NAME),
+            ConfigError::UnknownRule(id) => {
+                write!(f, "Unknown lint rule '{id}' in {}", Config::FILE_NAME)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
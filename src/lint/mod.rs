@@ -0,0 +1,409 @@
+mod config;
+mod rules;
+mod scope;
+
+pub use config::*;
+pub use rules::*;
+
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    token::{Op, Program, Span, Stmt, TokenTree},
+};
+
+/// A check run over a whole program by the [`Linter`].
+pub trait Rule {
+    /// The name the rule is reported and configured by, such as
+    /// `unused-variable`.
+    fn id(&self) -> &'static str;
+
+    /// The severity the rule reports with unless `lox.toml` says otherwise.
+    fn default_severity(&self) -> Severity;
+
+    /// Returns everything the rule finds wrong with `program`.
+    fn check(&self, program: &Program<'_>) -> Vec<Lint>;
+}
+
+/// A problem found by a [`Rule`].
+#[derive(Debug, Clone)]
+pub struct Lint {
+    span: Span,
+    message: String,
+    /// Another place involved, such as the declaration a variable shadows.
+    related: Option<(Span, String)>,
+}
+
+impl Lint {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            related: None,
+        }
+    }
+
+    pub fn with_related(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.related = Some((span, message.into()));
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Every rule that comes with rslox.
+pub fn builtin_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(UnusedVariable),
+        Box::new(UnusedParameter),
+        Box::new(UnreachableCode),
+        Box::new(Shadowing),
+        Box::new(SelfAssignment),
+        Box::new(MismatchedComparison),
+        Box::new(ConstantCondition),
+    ]
+}
+
+/// Runs a set of rules over programs and turns what they find into
+/// diagnostics.
+pub struct Linter {
+    rules: Vec<(Box<dyn Rule>, Severity)>,
+}
+
+impl Linter {
+    /// A linter with the built-in rules at the severities set by `config`.
+    pub fn new(config: &Config) -> Result<Self, ConfigError> {
+        let rules = builtin_rules();
+        if let Some(unknown) = config
+            .rule_ids()
+            .find(|id| !rules.iter().any(|rule| rule.id() == *id))
+        {
+            return Err(ConfigError::UnknownRule(unknown.to_string()));
+        }
+
+        let mut linter = Self { rules: Vec::new() };
+        for rule in rules {
+            let level = config
+                .level(rule.id())
+                .unwrap_or(Level::from(rule.default_severity()));
+            if let Some(severity) = level.severity() {
+                linter = linter.with_rule(rule, severity);
+            }
+        }
+        Ok(linter)
+    }
+
+    /// Adds a rule that reports with `severity`.
+    pub fn with_rule(mut self, rule: Box<dyn Rule>, severity: Severity) -> Self {
+        self.rules.push((rule, severity));
+        self
+    }
+
+    /// Checks `program` against every rule, in the order of the source.
+    pub fn check(&self, program: &Program<'_>) -> Vec<Diagnostic> {
+        let mut lints = self
+            .rules
+            .iter()
+            .flat_map(|(rule, severity)| {
+                rule.check(program)
+                    .into_iter()
+                    .map(move |lint| (lint, rule.id(), *severity))
+            })
+            .collect::<Vec<_>>();
+        lints.sort_by_key(|(lint, _, _)| lint.span.start());
+
+        lints
+            .into_iter()
+            .map(|(lint, id, severity)| {
+                let diagnostic = Diagnostic::new(severity, lint.message)
+                    .with_code(id)
+                    .with_primary(lint.span, "");
+                match lint.related {
+                    Some((span, message)) => diagnostic.with_secondary(span, message),
+                    None => diagnostic,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Calls `f` on every statement in `stmts` and those nested inside them,
+/// including in function and method bodies, outermost first.
+fn walk_stmts<'p, 'a>(stmts: &'p [Stmt<'a>], f: &mut impl FnMut(&'p Stmt<'a>)) {
+    for stmt in stmts {
+        f(stmt);
+        match stmt {
+            Stmt::Block(stmts) => walk_stmts(stmts, f),
+            Stmt::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                walk_stmts(std::slice::from_ref(then_branch.as_ref()), f);
+                if let Some(else_branch) = else_branch {
+                    walk_stmts(std::slice::from_ref(else_branch.as_ref()), f);
+                }
+            }
+            Stmt::While { body, .. } => walk_stmts(std::slice::from_ref(body.as_ref()), f),
+            Stmt::For {
+                initializer, body, ..
+            } => {
+                if let Some(initializer) = initializer {
+                    walk_stmts(std::slice::from_ref(initializer.as_ref()), f);
+                }
+                walk_stmts(std::slice::from_ref(body.as_ref()), f);
+            }
+            Stmt::Function(function) => walk_stmts(&function.body, f),
+            Stmt::Class { methods, .. } => {
+                for method in methods {
+                    walk_stmts(&method.body, f);
+                }
+            }
+            Stmt::Expression(_) | Stmt::Print(_) | Stmt::Var { .. } | Stmt::Return { .. } => {}
+        }
+    }
+}
+
+/// Calls `f` on every expression in `stmts` and every subexpression of them,
+/// outermost first.
+fn walk_exprs<'p, 'a>(stmts: &'p [Stmt<'a>], f: &mut impl FnMut(&'p TokenTree<'a>)) {
+    fn walk<'p, 'a>(expr: &'p TokenTree<'a>, f: &mut impl FnMut(&'p TokenTree<'a>)) {
+        f(expr);
+        if let TokenTree::Cons(_, operands, _) = expr {
+            for operand in operands {
+                walk(operand, f);
+            }
+        }
+    }
+
+    walk_stmts(stmts, &mut |stmt| match stmt {
+        Stmt::Expression(expr) | Stmt::Print(expr) => walk(expr, f),
+        Stmt::Var {
+            initializer: Some(expr),
+            ..
+        }
+        | Stmt::If {
+            condition: expr, ..
+        }
+        | Stmt::While {
+            condition: expr, ..
+        }
+        | Stmt::Return {
+            value: Some(expr), ..
+        }
+        | Stmt::Class {
+            superclass: Some(expr),
+            ..
+        } => walk(expr, f),
+        Stmt::For {
+            condition,
+            increment,
+            ..
+        } => {
+            for expr in condition.iter().chain(increment) {
+                walk(expr, f);
+            }
+        }
+        _ => {}
+    });
+}
+
+/// A span to point at for `stmt`, if it has anything to point at.
+fn stmt_span(stmt: &Stmt<'_>) -> Option<Span> {
+    match stmt {
+        Stmt::Expression(expr) | Stmt::Print(expr) => Some(expr.span()),
+        Stmt::Var { span, .. } | Stmt::Return { span, .. } | Stmt::Class { span, .. } => {
+            Some(*span)
+        }
+        Stmt::Function(function) => Some(function.span),
+        Stmt::Block(stmts) => stmts.iter().find_map(stmt_span),
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => Some(condition.span()),
+        Stmt::For {
+            initializer,
+            condition,
+            body,
+            ..
+        } => initializer
+            .as_deref()
+            .and_then(stmt_span)
+            .or(condition.as_ref().map(TokenTree::span))
+            .or_else(|| stmt_span(body)),
+    }
+}
+
+/// Strips any parentheses around `expr`.
+fn ungroup<'p, 'a>(mut expr: &'p TokenTree<'a>) -> &'p TokenTree<'a> {
+    while let TokenTree::Cons(Op::Group, operands, _) = expr {
+        expr = &operands[0];
+    }
+    expr
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Lexer, Parser};
+
+    use super::*;
+
+    /// The IDs of the rules that `source` breaks, in order, and the text each
+    /// points at.
+    fn lint<'a>(linter: &Linter, source: &'a str) -> Vec<(String, &'a str)> {
+        let (program, errors) = Parser::with_lexer(Lexer::new(source)).parse_program();
+        assert!(errors.is_empty(), "Failed to parse program: {:?}", errors);
+        linter
+            .check(&program)
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span().unwrap();
+                (
+                    diagnostic.code().unwrap().to_string(),
+                    &source[span.start()..span.end()],
+                )
+            })
+            .collect()
+    }
+
+    fn lints(source: &str) -> Vec<(String, &str)> {
+        lint(&Linter::new(&Config::default()).unwrap(), source)
+    }
+
+    fn ids(source: &str) -> Vec<String> {
+        lints(source).into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn clean_program() {
+        let source = "
+var a = 1;
+fun f(b) {
+    var c = a + b;
+    fun g() { return c; }
+    return g;
+}
+class A < B {
+    init(x) { this.x = x; }
+}
+if (a == 1) print f(2)();
+for (var i = 0; i < 3; i = i + 1) print i;
+while (true) {}";
+        assert_eq!(lints(source), []);
+    }
+
+    #[test]
+    fn unused_variables_and_parameters() {
+        assert_eq!(
+            lints("var a; fun f(b, _c, h) { var d = 1; var _e; { var g; g = 2; } }"),
+            [
+                ("unused-parameter".to_string(), "b"),
+                ("unused-parameter".to_string(), "h"),
+                ("unused-variable".to_string(), "d"),
+                ("unused-variable".to_string(), "g"),
+            ]
+        );
+        // Reading a variable in a closure uses it.
+        assert_eq!(
+            ids("fun f() { var a; fun g() { print a; } g(); }"),
+            [] as [&str; 0]
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        assert_eq!(
+            lints("fun f(a) { return a; print 1; print 2; }"),
+            [("unreachable-code".to_string(), "1")]
+        );
+        assert_eq!(
+            lints("fun f(a) { if (a) { return 1; } else return 2; var b = 3; print b; }"),
+            [("unreachable-code".to_string(), "b")]
+        );
+        assert_eq!(
+            ids("fun f(a) { if (a) return 1; print 2; }"),
+            [] as [&str; 0]
+        );
+    }
+
+    #[test]
+    fn shadowing() {
+        let source = "var a; fun f(a) { print a; { var a = 1; print a; } }";
+        let linter = Linter::new(&Config::default()).unwrap();
+        let (program, _) = Parser::with_lexer(Lexer::new(source)).parse_program();
+        let diagnostics = linter.check(&program);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.code().unwrap())
+                .collect::<Vec<_>>(),
+            ["shadowing", "shadowing"]
+        );
+        // The inner `a` shadows the parameter.
+        let related = diagnostics[1].labels()[1].span();
+        assert_eq!(related.start(), source.find("f(a").unwrap() + 2);
+        assert_eq!(&source[related.start()..related.end()], "a");
+    }
+
+    #[test]
+    fn self_assignment() {
+        assert_eq!(
+            lints("var a; a = a; a = (a); class A { f() { this.x = this.x; this.x = this.y; } }"),
+            [
+                ("self-assignment".to_string(), "a = a"),
+                ("self-assignment".to_string(), "a = (a)"),
+                ("self-assignment".to_string(), "this.x = this.x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn mismatched_comparisons() {
+        assert_eq!(
+            lints(
+                "var a; print a == 1; print 1 == \"1\"; print (a + 1) != nil; print -a < \"b\"; print \"${a}\" == \"\";"
+            ),
+            [
+                ("mismatched-comparison".to_string(), "1 == \"1\""),
+                ("mismatched-comparison".to_string(), "-a < \"b\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_conditions() {
+        assert_eq!(
+            lints(
+                "var a; if (true) print 1; if (!nil) print 2; if (a and false) print 3; if (\"\") print 4; if (a) print 5;"
+            ),
+            [
+                ("constant-condition".to_string(), "true"),
+                ("constant-condition".to_string(), "!nil"),
+                ("constant-condition".to_string(), "a and false"),
+                ("constant-condition".to_string(), "\"\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn configuration() {
+        let config =
+            Config::from_toml("[lint]\nunused-variable = \"off\"\nself-assignment = \"error\"")
+                .unwrap();
+        let linter = Linter::new(&config).unwrap();
+        let (program, _) =
+            Parser::with_lexer(Lexer::new("fun f() { var a; var b; b = b; }")).parse_program();
+        let diagnostics = linter.check(&program);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code(), Some("self-assignment"));
+        assert_eq!(diagnostics[0].severity(), Severity::Error);
+
+        let config = Config::from_toml("[lint]\nno-such-rule = \"warning\"").unwrap();
+        assert!(matches!(
+            Linter::new(&config),
+            Err(ConfigError::UnknownRule(_))
+        ));
+        assert!(Config::from_toml("[lint]\nshadowing = \"loud\"").is_err());
+    }
+}
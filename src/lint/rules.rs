@@ -0,0 +1,376 @@
+use crate::{
+    diagnostics::Severity,
+    token::{Atom, Op, Program, Stmt, TokenTree},
+};
+
+use super::{
+    Lint, Rule,
+    scope::{BindingKind, bindings},
+    stmt_span, ungroup, walk_exprs, walk_stmts,
+};
+
+/// Local variables, functions and classes that are never read. Names starting
+/// with an underscore are left alone.
+pub struct UnusedVariable;
+
+impl Rule for UnusedVariable {
+    fn id(&self) -> &'static str {
+        "unused-variable"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        bindings(program)
+            .into_iter()
+            .filter(|binding| {
+                binding.kind == BindingKind::Local
+                    && !binding.used
+                    && !binding.name.starts_with('_')
+            })
+            .map(|binding| Lint::new(binding.span, format!("'{}' is never used.", binding.name)))
+            .collect()
+    }
+}
+
+/// Function parameters that are never read. Names starting with an
+/// underscore are left alone.
+pub struct UnusedParameter;
+
+impl Rule for UnusedParameter {
+    fn id(&self) -> &'static str {
+        "unused-parameter"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Note
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        bindings(program)
+            .into_iter()
+            .filter(|binding| {
+                binding.kind == BindingKind::Parameter
+                    && !binding.used
+                    && !binding.name.starts_with('_')
+            })
+            .map(|binding| {
+                Lint::new(
+                    binding.span,
+                    format!("Parameter '{}' is never used.", binding.name),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Statements after a `return`, or after an `if` that returns from both of
+/// its branches.
+pub struct UnreachableCode;
+
+impl UnreachableCode {
+    fn always_returns(stmt: &Stmt<'_>) -> bool {
+        match stmt {
+            Stmt::Return { .. } => true,
+            Stmt::Block(stmts) => stmts.iter().any(Self::always_returns),
+            Stmt::If {
+                then_branch,
+                else_branch: Some(else_branch),
+                ..
+            } => Self::always_returns(then_branch) && Self::always_returns(else_branch),
+            _ => false,
+        }
+    }
+
+    fn check_stmts(stmts: &[Stmt<'_>], lints: &mut Vec<Lint>) {
+        if let Some(returns) = stmts.iter().position(Self::always_returns)
+            && let Some(span) = stmts[returns + 1..].iter().find_map(stmt_span)
+        {
+            lints.push(Lint::new(span, "Unreachable code."));
+        }
+    }
+}
+
+impl Rule for UnreachableCode {
+    fn id(&self) -> &'static str {
+        "unreachable-code"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        Self::check_stmts(program.declarations(), &mut lints);
+        walk_stmts(program.declarations(), &mut |stmt| match stmt {
+            Stmt::Block(stmts) => Self::check_stmts(stmts, &mut lints),
+            Stmt::Function(function) => Self::check_stmts(&function.body, &mut lints),
+            Stmt::Class { methods, .. } => {
+                for method in methods {
+                    Self::check_stmts(&method.body, &mut lints);
+                }
+            }
+            _ => {}
+        });
+        lints
+    }
+}
+
+/// Local variables and parameters that hide one with the same name from an
+/// enclosing scope.
+pub struct Shadowing;
+
+impl Rule for Shadowing {
+    fn id(&self) -> &'static str {
+        "shadowing"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Note
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        let bindings = bindings(program);
+        bindings
+            .iter()
+            .filter_map(|binding| {
+                let shadowed = &bindings[binding.shadows?];
+                Some(
+                    Lint::new(
+                        binding.span,
+                        format!("'{}' shadows an earlier declaration.", binding.name),
+                    )
+                    .with_related(shadowed.span, "shadowed declaration"),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Assignments of a variable or a field to itself, such as `a = a`.
+pub struct SelfAssignment;
+
+impl SelfAssignment {
+    /// Whether `a` and `b` are the same variable or field of a variable.
+    fn same_place(a: &TokenTree<'_>, b: &TokenTree<'_>) -> bool {
+        match (a, ungroup(b)) {
+            (TokenTree::Atom(Atom::Ident(a, _), _), TokenTree::Atom(Atom::Ident(b, _), _)) => {
+                a == b
+            }
+            (TokenTree::Atom(Atom::This(_), _), TokenTree::Atom(Atom::This(_), _)) => true,
+            (TokenTree::Cons(Op::Dot, a, _), TokenTree::Cons(Op::Dot, b, _)) => {
+                Self::same_place(&a[0], &b[0]) && Self::same_place(&a[1], &b[1])
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Rule for SelfAssignment {
+    fn id(&self) -> &'static str {
+        "self-assignment"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        walk_exprs(program.declarations(), &mut |expr| {
+            if let TokenTree::Cons(Op::Equal, operands, span) = expr
+                && Self::same_place(&operands[0], &operands[1])
+            {
+                lints.push(Lint::new(
+                    *span,
+                    "Assigning a variable to itself has no effect.",
+                ));
+            }
+        });
+        lints
+    }
+}
+
+/// The type an expression always has, where that is known without running it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Nil,
+    Bool,
+    Number,
+    String,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Nil => write!(f, "nil"),
+            Type::Bool => write!(f, "boolean"),
+            Type::Number => write!(f, "number"),
+            Type::String => write!(f, "string"),
+        }
+    }
+}
+
+/// Comparisons with a literal that can never succeed: equality between
+/// different types, and ordering anything but numbers.
+pub struct MismatchedComparison;
+
+impl MismatchedComparison {
+    fn static_type(expr: &TokenTree<'_>) -> Option<Type> {
+        match ungroup(expr) {
+            TokenTree::Atom(Atom::Nil, _) => Some(Type::Nil),
+            TokenTree::Atom(Atom::Bool(_), _) => Some(Type::Bool),
+            TokenTree::Atom(Atom::Number(_), _) => Some(Type::Number),
+            TokenTree::Atom(Atom::String(_), _) => Some(Type::String),
+            TokenTree::Atom(..) => None,
+            TokenTree::Cons(op, operands, _) => match op {
+                Op::Interpolate => Some(Type::String),
+                Op::Bang
+                | Op::EqualEqual
+                | Op::BangEqual
+                | Op::Less
+                | Op::LessEqual
+                | Op::Greater
+                | Op::GreaterEqual => Some(Type::Bool),
+                Op::Minus | Op::Star | Op::Slash => Some(Type::Number),
+                Op::Plus => match operands.as_slice() {
+                    [_] => Some(Type::Number),
+                    [a, b] => Self::static_type(a).filter(|&a| {
+                        Self::static_type(b) == Some(a) && matches!(a, Type::Number | Type::String)
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            },
+        }
+    }
+
+    fn is_literal(expr: &TokenTree<'_>) -> bool {
+        matches!(
+            ungroup(expr),
+            TokenTree::Atom(
+                Atom::Nil | Atom::Bool(_) | Atom::Number(_) | Atom::String(_),
+                _
+            )
+        )
+    }
+}
+
+impl Rule for MismatchedComparison {
+    fn id(&self) -> &'static str {
+        "mismatched-comparison"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        walk_exprs(program.declarations(), &mut |expr| {
+            let TokenTree::Cons(op, operands, span) = expr else {
+                return;
+            };
+            let [a, b] = operands.as_slice() else {
+                return;
+            };
+            if !Self::is_literal(a) && !Self::is_literal(b) {
+                return;
+            }
+            let (a, b) = (Self::static_type(a), Self::static_type(b));
+
+            let message = match op {
+                Op::EqualEqual | Op::BangEqual => {
+                    let (Some(a), Some(b)) = (a, b) else {
+                        return;
+                    };
+                    if a == b {
+                        return;
+                    }
+                    let result = matches!(op, Op::BangEqual);
+                    format!("Comparing a {a} with a {b} is always {result}.")
+                }
+                Op::Less | Op::LessEqual | Op::Greater | Op::GreaterEqual => {
+                    let Some(other) = [a, b].into_iter().flatten().find(|&ty| ty != Type::Number)
+                    else {
+                        return;
+                    };
+                    format!("Only numbers can be compared with '{op}', not a {other}.")
+                }
+                _ => return,
+            };
+            lints.push(Lint::new(*span, message));
+        });
+        lints
+    }
+}
+
+/// `if` statements whose condition is always truthy or always falsy.
+pub struct ConstantCondition;
+
+impl ConstantCondition {
+    /// The truthiness `expr` always has, if it doesn't depend on anything
+    /// known only when running.
+    fn truthiness(expr: &TokenTree<'_>) -> Option<bool> {
+        match ungroup(expr) {
+            TokenTree::Atom(Atom::Nil, _) => Some(false),
+            TokenTree::Atom(Atom::Bool(b), _) => Some(*b),
+            TokenTree::Atom(Atom::Number(_) | Atom::String(_), _) => Some(true),
+            TokenTree::Atom(..) => None,
+            TokenTree::Cons(Op::Interpolate, ..) => Some(true),
+            TokenTree::Cons(Op::Bang, operands, _) if operands.len() == 1 => {
+                Self::truthiness(&operands[0]).map(|b| !b)
+            }
+            // `a and false` is always falsy and `a or true` always truthy.
+            TokenTree::Cons(Op::And, operands, _) => {
+                match (
+                    Self::truthiness(&operands[0]),
+                    Self::truthiness(&operands[1]),
+                ) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), b) => b,
+                    _ => None,
+                }
+            }
+            TokenTree::Cons(Op::Or, operands, _) => {
+                match (
+                    Self::truthiness(&operands[0]),
+                    Self::truthiness(&operands[1]),
+                ) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), b) => b,
+                    _ => None,
+                }
+            }
+            TokenTree::Cons(..) => None,
+        }
+    }
+}
+
+impl Rule for ConstantCondition {
+    fn id(&self) -> &'static str {
+        "constant-condition"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, program: &Program<'_>) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        walk_stmts(program.declarations(), &mut |stmt| {
+            if let Stmt::If { condition, .. } = stmt
+                && let Some(truthy) = Self::truthiness(condition)
+            {
+                lints.push(Lint::new(
+                    condition.span(),
+                    format!("This condition is always {truthy}."),
+                ));
+            }
+        });
+        lints
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use crate::token::{Atom, Function, Op, Program, Span, Stmt, TokenTree};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum BindingKind {
+    Global,
+    Local,
+    Parameter,
+}
+
+/// A declared variable, function, class or parameter.
+#[derive(Debug)]
+pub(super) struct Binding<'p> {
+    pub name: &'p str,
+    pub kind: BindingKind,
+    /// The span of the name.
+    pub span: Span,
+    /// Whether the binding is ever read. Assigning to it doesn't count.
+    pub used: bool,
+    /// The binding from an enclosing scope that this one hides.
+    pub shadows: Option<usize>,
+}
+
+/// Every binding declared in `program`, in the order of their declarations,
+/// found by walking the scopes the same way the resolver does.
+pub(super) fn bindings<'p>(program: &'p Program<'_>) -> Vec<Binding<'p>> {
+    let mut scopes = Scopes {
+        bindings: Vec::new(),
+        scopes: vec![HashMap::new()],
+    };
+    scopes.stmts(program.declarations());
+    scopes.bindings
+}
+
+struct Scopes<'p> {
+    bindings: Vec<Binding<'p>>,
+    /// The bindings declared in each scope by name, the global one first.
+    scopes: Vec<HashMap<&'p str, usize>>,
+}
+
+impl<'p> Scopes<'p> {
+    fn stmts(&mut self, stmts: &'p [Stmt<'_>]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'p Stmt<'_>) {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) => self.expr(expr),
+            Stmt::Var {
+                name,
+                initializer,
+                span,
+            } => {
+                if let Some(initializer) = initializer {
+                    self.expr(initializer);
+                }
+                self.declare(name, *span, false);
+            }
+            Stmt::Block(stmts) => self.in_scope(|scopes| scopes.stmts(stmts)),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmt(body);
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => self.in_scope(|scopes| {
+                if let Some(initializer) = initializer {
+                    scopes.stmt(initializer);
+                }
+                for expr in condition.iter().chain(increment) {
+                    scopes.expr(expr);
+                }
+                scopes.stmt(body);
+            }),
+            Stmt::Function(function) => {
+                self.declare(&function.name, function.span, false);
+                self.function(function);
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                span,
+            } => {
+                self.declare(name, *span, false);
+                if let Some(superclass) = superclass {
+                    self.expr(superclass);
+                }
+                for method in methods {
+                    self.function(method);
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, function: &'p Function<'_>) {
+        self.in_scope(|scopes| {
+            for (param, span) in &function.params {
+                scopes.declare(param, *span, true);
+            }
+            scopes.stmts(&function.body);
+        });
+    }
+
+    fn expr(&mut self, expr: &'p TokenTree<'_>) {
+        match expr {
+            TokenTree::Atom(Atom::Ident(name, _), _) => {
+                if let Some(binding) = self.lookup(name) {
+                    self.bindings[binding].used = true;
+                }
+            }
+            TokenTree::Atom(..) => {}
+            // Assigning to a variable doesn't read it.
+            TokenTree::Cons(Op::Equal, operands, _)
+                if matches!(operands[0], TokenTree::Atom(Atom::Ident(..), _)) =>
+            {
+                self.expr(&operands[1]);
+            }
+            // The property name is not a variable.
+            TokenTree::Cons(Op::Dot, operands, _) => self.expr(&operands[0]),
+            TokenTree::Cons(_, operands, _) => {
+                for operand in operands {
+                    self.expr(operand);
+                }
+            }
+        }
+    }
+
+    fn in_scope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &'p str, span: Span, parameter: bool) {
+        let kind = match (parameter, self.scopes.len()) {
+            (true, _) => BindingKind::Parameter,
+            (false, 1) => BindingKind::Global,
+            (false, _) => BindingKind::Local,
+        };
+        let (current, enclosing) = self.scopes.split_last().expect("the global scope");
+        // Redeclaring a global replaces it rather than hiding it.
+        let shadows = if current.contains_key(name) {
+            None
+        } else {
+            enclosing
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name).copied())
+        };
+
+        self.bindings.push(Binding {
+            name,
+            kind,
+            span,
+            used: false,
+            shadows,
+        });
+        let index = self.bindings.len() - 1;
+        self.scopes
+            .last_mut()
+            .expect("the global scope")
+            .insert(name, index);
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+}
@@ -1,51 +1,337 @@
-use clap::{Parser, Subcommand};
-use std::{path::PathBuf, process::ExitCode};
+use clap::{Parser, Subcommand, ValueEnum};
+use rslox::{
+    diagnostics::{Diagnostic, Format, Renderer, Severity},
+    error::Error,
+    formatter::Formatter,
+    lint::{Config, Linter},
+    repl::Repl,
+};
+use serde::Serialize;
+use std::{io::IsTerminal, path::PathBuf, process::ExitCode};
 #[derive(Parser, Debug)]
 #[command(version)]
 struct Args {
+    /// How errors are printed.
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human, global = true)]
+    error_format: ErrorFormat,
+    /// Starts the interactive prompt if omitted.
     #[command(subcommand)]
-    command: Command,
+    command: Option<Command>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
-    Tokenize { filename: PathBuf },
-    Parse { filename: PathBuf },
+    Tokenize {
+        filename: PathBuf,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    Parse {
+        filename: PathBuf,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    Evaluate {
+        filename: PathBuf,
+    },
+    Run {
+        filename: PathBuf,
+    },
+    Repl,
+    Fmt {
+        /// Exit with an error instead of writing the files if any would change.
+        #[arg(long)]
+        check: bool,
+        /// The line width past which lists are wrapped.
+        #[arg(long, default_value_t = 100)]
+        width: usize,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Checks files against the rules configured in the nearest lox.toml.
+    Lint {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ErrorFormat {
+    /// The source line with the error underlined.
+    Human,
+    /// One line per error.
+    Short,
+    /// One JSON object per error.
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    /// The Crafting Interpreters test suite format.
+    Text,
+    /// A single pretty-printed JSON document.
+    Json,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl From<ErrorFormat> for Format {
+    fn from(format: ErrorFormat) -> Self {
+        match format {
+            ErrorFormat::Human => Format::Human,
+            ErrorFormat::Short => Format::Short,
+            ErrorFormat::Json => Format::Json,
+        }
+    }
+}
+
+fn print_json(value: &impl Serialize, format: OutputFormat) {
+    let json = if format == OutputFormat::Json {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    println!("{}", json.expect("tokens and trees always serialize"));
+}
+
+/// A renderer for diagnostics about `source`, in color if they go to a
+/// terminal.
+fn renderer<'a>(source: &'a str, path: &'a str, error_format: Format) -> Renderer<'a> {
+    Renderer::new(source, path, error_format)
+        .with_color(error_format != Format::Json && std::io::stderr().is_terminal())
+}
+
+fn report(renderer: &Renderer, error: &Error) {
+    eprintln!("{}", renderer.render(&Diagnostic::from(error)));
+}
+
+/// Formats `files` in place, or with `check` only reports the ones that
+/// aren't formatted.
+fn format_files(files: &[PathBuf], check: bool, width: usize, error_format: Format) -> ExitCode {
+    let formatter = Formatter::new().with_width(width);
+    let mut exit_code = 0;
+    for file in files {
+        let path = file.to_string_lossy();
+        let content = match std::fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Failed to read {path}: {e}");
+                exit_code = exit_code.max(66);
+                continue;
+            }
+        };
+        match formatter.format(&content) {
+            Ok(formatted) if formatted == content => {}
+            Ok(_) if check => {
+                eprintln!("{path} is not formatted");
+                exit_code = exit_code.max(1);
+            }
+            Ok(formatted) => {
+                if let Err(e) = std::fs::write(file, formatted) {
+                    eprintln!("Failed to write {path}: {e}");
+                    exit_code = exit_code.max(74);
+                }
+            }
+            Err(errors) => {
+                let renderer = renderer(&content, &path, error_format);
+                for e in errors {
+                    report(&renderer, &e);
+                }
+                exit_code = exit_code.max(65);
+            }
+        }
+    }
+    ExitCode::from(exit_code)
+}
+
+/// Lints `files`, failing if there are syntax errors or lints at the error
+/// severity.
+fn lint_files(files: &[PathBuf], error_format: Format) -> ExitCode {
+    let linter = std::env::current_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| Config::discover(&dir).map_err(|e| e.to_string()))
+        .and_then(|config| Linter::new(&config).map_err(|e| e.to_string()));
+    let linter = match linter {
+        Ok(linter) => linter,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(78);
+        }
+    };
+
+    let mut exit_code = 0;
+    for file in files {
+        let path = file.to_string_lossy();
+        let content = match std::fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Failed to read {path}: {e}");
+                exit_code = exit_code.max(66);
+                continue;
+            }
+        };
+        let renderer = renderer(&content, &path, error_format);
+
+        let (program, errors) =
+            rslox::Parser::with_lexer(rslox::Lexer::new(&content)).parse_program();
+        if !errors.is_empty() {
+            for e in errors {
+                report(&renderer, &e);
+            }
+            exit_code = exit_code.max(65);
+            continue;
+        }
+        if let Err(errors) = rslox::Resolver::new().resolve(&program) {
+            for e in errors {
+                report(&renderer, &e);
+            }
+            exit_code = exit_code.max(65);
+        }
+
+        for diagnostic in linter.check(&program) {
+            if diagnostic.severity() == Severity::Error {
+                exit_code = exit_code.max(1);
+            }
+            eprintln!("{}", renderer.render(&diagnostic));
+        }
+    }
+    ExitCode::from(exit_code)
 }
 
 fn main() -> ExitCode {
     let args = Args::parse();
-    let mut exit_code = ExitCode::from(0);
     match args.command {
-        Command::Tokenize { filename } => {
-            let content = std::fs::read_to_string(&filename).expect("Failed to read the file");
-            let mut lexer = rslox::Lexer::new(content.as_str());
-            while let Some(token) = lexer.next() {
+        None | Some(Command::Run { .. } | Command::Evaluate { .. } | Command::Repl) => {
+            // Deeply recursive Lox programs need more stack than the main thread has.
+            std::thread::Builder::new()
+                .stack_size(rslox::interpreter::STACK_SIZE)
+                .spawn(|| execute(args))
+                .expect("Failed to spawn the interpreter thread")
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }
+        _ => execute(args),
+    }
+}
+
+fn execute(args: Args) -> ExitCode {
+    let mut exit_code = ExitCode::from(0);
+    let command = args.command.unwrap_or(Command::Repl);
+    let filename = match &command {
+        Command::Tokenize { filename, .. }
+        | Command::Parse { filename, .. }
+        | Command::Evaluate { filename }
+        | Command::Run { filename } => filename,
+        Command::Repl => {
+            if let Err(e) = Repl::new(args.error_format.into()).run() {
+                eprintln!("{e}");
+                return ExitCode::from(74);
+            }
+            return exit_code;
+        }
+        Command::Fmt {
+            check,
+            width,
+            files,
+        } => return format_files(files, *check, *width, args.error_format.into()),
+        Command::Lint { files } => return lint_files(files, args.error_format.into()),
+    };
+    let content = std::fs::read_to_string(filename).expect("Failed to read the file");
+    let path = filename.to_string_lossy();
+    let error_format = Format::from(args.error_format);
+    let renderer = renderer(&content, &path, error_format);
+
+    match command {
+        Command::Tokenize { format, .. } => {
+            let lexer = rslox::Lexer::new(content.as_str());
+            let mut tokens = Vec::new();
+            for token in lexer {
                 match token {
-                    Ok(t) => println!("{}", t),
+                    Ok(t) => match format {
+                        OutputFormat::Text => println!("{}", t),
+                        OutputFormat::Json => tokens.push(t),
+                        OutputFormat::Jsonl => print_json(&t, format),
+                    },
                     Err(e) => {
                         exit_code = ExitCode::from(65);
-                        eprintln!("{}", e)
+                        report(&renderer, &e);
                     }
                 }
             }
-            println!("EOF  null");
+            match format {
+                OutputFormat::Text => println!("EOF  null"),
+                OutputFormat::Json => print_json(&tokens, format),
+                OutputFormat::Jsonl => {}
+            }
         }
-        Command::Parse { filename } => {
-            let content = std::fs::read_to_string(&filename).expect("Failed to read the file");
+        Command::Parse { format, .. } => match rslox::Parser::parse_lenient(&content) {
+            Ok(program) => match format {
+                OutputFormat::Text => print!("{}", program),
+                OutputFormat::Json => print_json(&program, format),
+                OutputFormat::Jsonl => {
+                    for declaration in program.declarations() {
+                        print_json(declaration, format);
+                    }
+                }
+            },
+            Err(errors) => {
+                exit_code = ExitCode::from(65);
+                for e in errors {
+                    report(&renderer, &e);
+                }
+            }
+        },
+        Command::Evaluate { .. } => {
             let lexer = rslox::Lexer::new(content.as_str());
             let mut parser = rslox::Parser::with_lexer(lexer);
             match parser.parse() {
-                Ok(tree) => {
-                    println!("{}", tree);
-                }
+                Ok(tree) => match rslox::interpreter::Interpreter::new().evaluate(&tree) {
+                    Ok(value) => println!("{}", value),
+                    Err(e) => {
+                        exit_code = ExitCode::from(70);
+                        report(&renderer, &e);
+                    }
+                },
                 Err(e) => {
                     exit_code = ExitCode::from(65);
-                    eprintln!("{}", e);
+                    report(&renderer, &e);
+                }
+            }
+        }
+        Command::Run { .. } => {
+            let lexer = rslox::Lexer::new(content.as_str());
+            let mut parser = rslox::Parser::with_lexer(lexer);
+            match parser.parse_program() {
+                (program, errors) if errors.is_empty() => {
+                    match rslox::Resolver::new().resolve(&program) {
+                        Ok(locals) => {
+                            let mut interpreter = rslox::interpreter::Interpreter::new();
+                            interpreter.add_locals(locals);
+                            if let Err(e) = interpreter.interpret(&program) {
+                                exit_code = ExitCode::from(70);
+                                report(&renderer, &e);
+                            }
+                        }
+                        Err(errors) => {
+                            exit_code = ExitCode::from(65);
+                            for e in errors {
+                                report(&renderer, &e);
+                            }
+                        }
+                    }
+                }
+                (_, errors) => {
+                    exit_code = ExitCode::from(65);
+                    for e in errors {
+                        report(&renderer, &e);
+                    }
                 }
             }
         }
+        Command::Repl | Command::Fmt { .. } | Command::Lint { .. } => {
+            unreachable!("handled before reading a file")
+        }
     }
 
-    return exit_code;
+    exit_code
 }
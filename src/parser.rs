@@ -1,114 +1,509 @@
 use crate::{
-    error::{Error, ParseError, ParseErrorKind},
+    error::{Error, LexingError, LexingErrorKind, ParseError, ParseErrorKind},
     lexer::Lexer,
-    token::{Atom, Keyword, Literal, Op, Operator, Token, TokenTree, TokenType, UnaryOperator},
+    token::{
+        Atom, BinaryOperator, Function, Keyword, Literal, NodeId, Op, Operator, Program, Span,
+        Stmt, Token, TokenTree, TokenType, UnaryOperator,
+    },
 };
+use std::{borrow::Cow, rc::Rc};
+
+/// The maximum number of arguments a call, or parameters a function, can have.
+const MAX_ARGUMENTS: usize = 255;
 
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
+    /// The type of the last token consumed, used to find statement boundaries
+    /// when recovering from an error.
+    previous: Option<TokenType>,
+    errors: Vec<Error>,
 }
 
 impl<'a> Parser<'a> {
     pub fn with_lexer(lexer: Lexer<'a>) -> Self {
-        Self { lexer }
+        Self {
+            lexer,
+            previous: None,
+            errors: Vec::new(),
+        }
     }
 
     pub fn parse(&mut self) -> Result<TokenTree<'a>, Error> {
         self.parse_expr(0)
     }
 
+    /// Parses `source` as a program, letting a lone expression or statement
+    /// leave off its trailing semicolon.
+    ///
+    /// The program is copied out of `source`, as the missing semicolon may
+    /// have to be added to a copy of it.
+    pub fn parse_lenient(source: &str) -> Result<Program<'static>, Vec<Error>> {
+        let (program, errors) = Parser::with_lexer(Lexer::new(source)).parse_program();
+        if errors.is_empty() {
+            return Ok(program.into_owned());
+        }
+
+        let trimmed = source.trim_end();
+        if !trimmed.ends_with([';', '}']) {
+            let retry = format!("{trimmed};");
+            let (program, retry_errors) = Parser::with_lexer(Lexer::new(&retry)).parse_program();
+            if retry_errors.is_empty() {
+                return Ok(program.into_owned());
+            }
+        }
+        Err(errors)
+    }
+
+    /// Parses the whole input as a list of declarations.
+    ///
+    /// Parsing carries on past syntax errors, so this returns every error in
+    /// the input along with the declarations that did parse.
+    pub fn parse_program(&mut self) -> (Program<'a>, Vec<Error>) {
+        let mut declarations = Vec::new();
+        while self.lexer.peek().is_some() {
+            declarations.extend(self.declaration());
+        }
+        (Program::new(declarations), std::mem::take(&mut self.errors))
+    }
+
+    /// Parses a declaration, recording the error and skipping to the next
+    /// statement if it is malformed.
+    fn declaration(&mut self) -> Option<Stmt<'a>> {
+        match self.parse_declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(e) => {
+                self.errors.push(e);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    /// Discards tokens until the start of the next statement.
+    fn synchronize(&mut self) {
+        let semicolon = TokenType::Operator(Operator::Unary(UnaryOperator::Selmicolon));
+        if self.previous == Some(semicolon) {
+            return;
+        }
+
+        while let Some(token) = self.lexer.peek() {
+            match token.as_ref().map(|token| token.ty()) {
+                Ok(ty) if ty == semicolon => {
+                    self.advance();
+                    return;
+                }
+                Ok(TokenType::Keyword(
+                    Keyword::Class
+                    | Keyword::Fun
+                    | Keyword::Var
+                    | Keyword::For
+                    | Keyword::If
+                    | Keyword::While
+                    | Keyword::Print
+                    | Keyword::Return,
+                )) => return,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn parse_declaration(&mut self) -> Result<Stmt<'a>, Error> {
+        if self.consume(TokenType::Keyword(Keyword::Var)) {
+            self.parse_var_declaration()
+        } else if self.consume(TokenType::Keyword(Keyword::Fun)) {
+            Ok(Stmt::Function(self.parse_function()?))
+        } else if self.consume(TokenType::Keyword(Keyword::Class)) {
+            self.parse_class_declaration()
+        } else {
+            self.parse_statement()
+        }
+    }
+
+    fn parse_var_declaration(&mut self) -> Result<Stmt<'a>, Error> {
+        let (name, span) = self.expect_identifier()?;
+        let initializer =
+            if self.consume(TokenType::Operator(Operator::Binary(BinaryOperator::Equal))) {
+                Some(self.parse_expr(0)?)
+            } else {
+                None
+            };
+        self.expect(UnaryOperator::Selmicolon)?;
+
+        Ok(Stmt::Var {
+            name,
+            initializer,
+            span,
+        })
+    }
+
+    fn parse_function(&mut self) -> Result<Rc<Function<'a>>, Error> {
+        let (name, span) = self.expect_identifier()?;
+
+        self.expect(UnaryOperator::LeftParen)?;
+        let mut params = Vec::new();
+        if !self.consume(TokenType::Operator(Operator::Unary(
+            UnaryOperator::RightParen,
+        ))) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    return Err(Error::ParseError(ParseError::new(
+                        ParseErrorKind::TooManyParameters,
+                        self.next_span(),
+                    )));
+                }
+                params.push(self.expect_identifier()?);
+                if !self.consume(TokenType::Operator(Operator::Unary(UnaryOperator::Comma))) {
+                    break;
+                }
+            }
+            self.expect(UnaryOperator::RightParen)?;
+        }
+
+        self.expect(UnaryOperator::LeftBrace)?;
+        let body = self.parse_block()?;
+
+        Ok(Rc::new(Function {
+            name,
+            params,
+            body,
+            span,
+        }))
+    }
+
+    fn parse_class_declaration(&mut self) -> Result<Stmt<'a>, Error> {
+        let (name, span) = self.expect_identifier()?;
+
+        let superclass =
+            if self.consume(TokenType::Operator(Operator::Binary(BinaryOperator::Less))) {
+                let (superclass, span) = self.expect_identifier()?;
+                Some(TokenTree::Atom(
+                    Atom::Ident(superclass, NodeId::fresh()),
+                    span,
+                ))
+            } else {
+                None
+            };
+
+        self.expect(UnaryOperator::LeftBrace)?;
+        let mut methods = Vec::new();
+        while !self.consume(TokenType::Operator(Operator::Unary(
+            UnaryOperator::RightBrace,
+        ))) {
+            methods.push(self.parse_function()?);
+        }
+
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+            span,
+        })
+    }
+
+    fn parse_statement(&mut self) -> Result<Stmt<'a>, Error> {
+        if self.consume(TokenType::Keyword(Keyword::Print)) {
+            let value = self.parse_expr(0)?;
+            self.expect(UnaryOperator::Selmicolon)?;
+            Ok(Stmt::Print(value))
+        } else if let Some(keyword) = self.consume_token(TokenType::Keyword(Keyword::Return)) {
+            let span = keyword.span();
+            let value = if self.check(TokenType::Operator(Operator::Unary(
+                UnaryOperator::Selmicolon,
+            ))) {
+                None
+            } else {
+                Some(self.parse_expr(0)?)
+            };
+            self.expect(UnaryOperator::Selmicolon)?;
+            Ok(Stmt::Return { value, span })
+        } else if self.consume(TokenType::Keyword(Keyword::If)) {
+            self.expect(UnaryOperator::LeftParen)?;
+            let condition = self.parse_expr(0)?;
+            self.expect(UnaryOperator::RightParen)?;
+
+            let then_branch = Box::new(self.parse_statement()?);
+            let else_branch = if self.consume(TokenType::Keyword(Keyword::Else)) {
+                Some(Box::new(self.parse_statement()?))
+            } else {
+                None
+            };
+
+            Ok(Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            })
+        } else if self.consume(TokenType::Keyword(Keyword::While)) {
+            self.expect(UnaryOperator::LeftParen)?;
+            let condition = self.parse_expr(0)?;
+            self.expect(UnaryOperator::RightParen)?;
+            let body = Box::new(self.parse_statement()?);
+
+            Ok(Stmt::While { condition, body })
+        } else if self.consume(TokenType::Keyword(Keyword::For)) {
+            self.parse_for_statement()
+        } else if self.consume(TokenType::Operator(Operator::Unary(
+            UnaryOperator::LeftBrace,
+        ))) {
+            Ok(Stmt::Block(self.parse_block()?))
+        } else {
+            let expr = self.parse_expr(0)?;
+            self.expect(UnaryOperator::Selmicolon)?;
+            Ok(Stmt::Expression(expr))
+        }
+    }
+
+    fn parse_for_statement(&mut self) -> Result<Stmt<'a>, Error> {
+        self.expect(UnaryOperator::LeftParen)?;
+
+        let initializer = if self.consume(TokenType::Operator(Operator::Unary(
+            UnaryOperator::Selmicolon,
+        ))) {
+            None
+        } else if self.consume(TokenType::Keyword(Keyword::Var)) {
+            Some(Box::new(self.parse_var_declaration()?))
+        } else {
+            let expr = self.parse_expr(0)?;
+            self.expect(UnaryOperator::Selmicolon)?;
+            Some(Box::new(Stmt::Expression(expr)))
+        };
+
+        let condition = if self.check(TokenType::Operator(Operator::Unary(
+            UnaryOperator::Selmicolon,
+        ))) {
+            None
+        } else {
+            Some(self.parse_expr(0)?)
+        };
+        self.expect(UnaryOperator::Selmicolon)?;
+
+        let increment = if self.check(TokenType::Operator(Operator::Unary(
+            UnaryOperator::RightParen,
+        ))) {
+            None
+        } else {
+            Some(self.parse_expr(0)?)
+        };
+        self.expect(UnaryOperator::RightParen)?;
+
+        let body = Box::new(self.parse_statement()?);
+
+        Ok(Stmt::For {
+            initializer,
+            condition,
+            increment,
+            body,
+        })
+    }
+
+    /// Parses the declarations of a block whose opening brace was already consumed.
+    fn parse_block(&mut self) -> Result<Vec<Stmt<'a>>, Error> {
+        let mut stmts = Vec::new();
+        while !self.consume(TokenType::Operator(Operator::Unary(
+            UnaryOperator::RightBrace,
+        ))) {
+            if self.lexer.peek().is_none() {
+                return Err(Error::UnexpectedEndOfInput(self.lexer.end_of_input()));
+            }
+            stmts.extend(self.declaration());
+        }
+        Ok(stmts)
+    }
+
+    /// Returns `true` if the next token is of type `ty`.
+    fn check(&mut self, ty: TokenType) -> bool {
+        matches!(self.lexer.peek(), Some(Ok(token)) if token.ty() == ty)
+    }
+
+    /// Consumes the next token if it is of type `ty`.
+    fn consume(&mut self, ty: TokenType) -> bool {
+        self.consume_token(ty).is_some()
+    }
+
+    /// Consumes and returns the next token if it is of type `ty`.
+    fn consume_token(&mut self, ty: TokenType) -> Option<Token<'a>> {
+        if self.check(ty) {
+            self.advance().and_then(Result::ok)
+        } else {
+            None
+        }
+    }
+
+    /// The span of the next token, or of the end of input if there is none.
+    fn next_span(&mut self) -> Span {
+        match self.lexer.peek() {
+            Some(Ok(token)) => token.span(),
+            Some(Err(e)) => e.span(),
+            None => self.lexer.end_of_input(),
+        }
+    }
+
+    /// Consumes the next token.
+    fn advance(&mut self) -> Option<Result<Token<'a>, Error>> {
+        let token = self.lexer.next();
+        self.previous = match &token {
+            Some(Ok(token)) => Some(token.ty()),
+            _ => None,
+        };
+        token
+    }
+
+    /// Consumes the next token, failing if it is not of type `expected`.
+    fn expect_type(&mut self, expected: TokenType) -> Result<Token<'a>, Error> {
+        match self.advance() {
+            Some(Ok(token)) if token.ty() == expected => Ok(token),
+            Some(Ok(token)) => Err(Error::LexingError(LexingError::new(
+                LexingErrorKind::UnexpectedToken {
+                    expected,
+                    found: token.ty(),
+                },
+                token.span(),
+            ))),
+            Some(Err(e)) => Err(e),
+            None => Err(Error::UnexpectedEndOfInput(self.lexer.end_of_input())),
+        }
+    }
+
+    fn expect(&mut self, op: UnaryOperator) -> Result<Token<'a>, Error> {
+        self.expect_type(TokenType::Operator(Operator::Unary(op)))
+    }
+
+    /// Expects an identifier, returning its normalized name and span.
+    fn expect_identifier(&mut self) -> Result<(Cow<'a, str>, Span), Error> {
+        self.expect_type(TokenType::Literal(Literal::Identifier))
+            .map(|token| (Token::normalize(token.lexeme()), token.span()))
+    }
+
     fn parse_expr(&mut self, min_bp: u8) -> Result<TokenTree<'a>, Error> {
-        let lhs = match self.lexer.next() {
+        let lhs = match self.advance() {
             Some(Ok(token)) => token,
             Some(Err(e)) => return Err(e),
             None => {
-                return Err(Error::ParseError(ParseError::with_line(
+                return Err(Error::ParseError(ParseError::new(
                     ParseErrorKind::InvalidExpression(String::new()),
-                    self.lexer.line(),
+                    self.lexer.end_of_input(),
                 )));
             }
         };
 
+        let span = lhs.span();
         let mut lhs = match lhs.ty() {
             TokenType::Operator(Operator::Unary(op)) => match op {
                 UnaryOperator::LeftParen => {
                     let lhs = self.parse_expr(0)?;
-
-                    self.lexer.expect(TokenType::Operator(Operator::Unary(
-                        UnaryOperator::RightParen,
-                    )))?;
-
-                    TokenTree::Cons(Op::Group, vec![lhs])
+                    let right_paren = self.expect(UnaryOperator::RightParen)?;
+                    TokenTree::Cons(Op::Group, vec![lhs], span.to(right_paren.span()))
                 }
                 UnaryOperator::Bang | UnaryOperator::Minus | UnaryOperator::Plus => {
                     // Safe to unwrap as we checked the token type
-                    let op: Op = op.try_into()?;
+                    let op: Op = op.try_into().unwrap();
                     let ((), r_bp) = op.prefix_binding_power().unwrap();
                     let rhs = self.parse_expr(r_bp)?;
-                    TokenTree::Cons(op, vec![rhs])
+                    let span = span.to(rhs.span());
+                    TokenTree::Cons(op, vec![rhs], span)
                 }
                 _ => {
-                    return Err(Error::ParseError(ParseError::with_line(
+                    return Err(Error::ParseError(ParseError::new(
                         ParseErrorKind::InvalidExpression(lhs.lexeme().to_string()),
-                        self.lexer.line(),
+                        span,
                     )));
                 }
             },
             TokenType::Literal(lit) => match lit {
-                Literal::String => TokenTree::Atom(Atom::String(Token::unescape(lhs.lexeme()))),
-                Literal::Identifier => TokenTree::Atom(Atom::Ident(lhs.lexeme())),
-                Literal::Number(n) => TokenTree::Atom(Atom::Number(n)),
+                Literal::String => {
+                    TokenTree::Atom(Atom::String(Token::unescape(lhs.lexeme())), span)
+                }
+                Literal::Interpolation => self.parse_interpolation(lhs)?,
+                Literal::Identifier => TokenTree::Atom(
+                    Atom::Ident(Token::normalize(lhs.lexeme()), NodeId::fresh()),
+                    span,
+                ),
+                Literal::Number(n) => TokenTree::Atom(Atom::Number(n), span),
             },
             TokenType::Keyword(kw) => match kw {
-                Keyword::True => TokenTree::Atom(Atom::Bool(true)),
-                Keyword::False => TokenTree::Atom(Atom::Bool(false)),
-                Keyword::Nil => TokenTree::Atom(Atom::Nil),
-                Keyword::This => TokenTree::Atom(Atom::This),
-                Keyword::Super => TokenTree::Atom(Atom::Super),
+                Keyword::True => TokenTree::Atom(Atom::Bool(true), span),
+                Keyword::False => TokenTree::Atom(Atom::Bool(false), span),
+                Keyword::Nil => TokenTree::Atom(Atom::Nil, span),
+                Keyword::This => TokenTree::Atom(Atom::This(NodeId::fresh()), span),
+                Keyword::Super => {
+                    self.expect(UnaryOperator::Dot)?;
+                    let (method, method_span) = self.expect_identifier()?;
+                    let method = TokenTree::Atom(Atom::Ident(method, NodeId::fresh()), method_span);
+                    TokenTree::Cons(
+                        Op::Dot,
+                        vec![TokenTree::Atom(Atom::Super(NodeId::fresh()), span), method],
+                        span.to(method_span),
+                    )
+                }
                 Keyword::Print | Keyword::Return => {
                     // Safe to unwrap as we checked the token type
-                    let op: Op = kw.try_into()?;
+                    let op: Op = kw.try_into().unwrap();
                     let ((), r_bp) = op.prefix_binding_power().unwrap();
                     let rhs = self.parse_expr(r_bp)?;
-                    TokenTree::Cons(op, vec![rhs])
+                    let span = span.to(rhs.span());
+                    TokenTree::Cons(op, vec![rhs], span)
                 }
                 _ => {
                     return Err(Error::ParseError(ParseError::new(
                         ParseErrorKind::UnexpectedKeyword(kw),
+                        span,
                     )));
                 }
             },
             _ => {
                 return Err(Error::ParseError(ParseError::new(
                     ParseErrorKind::UnexpectedToken(lhs.ty(), lhs.lexeme().to_string()),
+                    span,
                 )));
             }
         };
 
         loop {
-            let op: Op = match self.lexer.peek() {
-                Some(token) => {
-                    let token = token?;
-                    match token.ty() {
-                        TokenType::Operator(Operator::Unary(UnaryOperator::RightParen)) => break,
-                        TokenType::Operator(op) => op.try_into()?,
-                        ty => {
-                            return Err(Error::ParseError(ParseError::new(
-                                ParseErrorKind::UnexpectedToken(ty, token.lexeme().to_string()),
-                            )));
-                        }
-                    }
+            let token = match self.lexer.peek() {
+                None => break,
+                Some(Ok(token)) => *token,
+                Some(Err(_)) => return Err(self.advance().unwrap().unwrap_err()),
+            };
+            let op: Op = match token.ty() {
+                TokenType::Operator(Operator::Unary(
+                    UnaryOperator::RightParen
+                    | UnaryOperator::RightBrace
+                    | UnaryOperator::Selmicolon
+                    | UnaryOperator::Comma
+                    | UnaryOperator::Colon,
+                )) => break,
+                // The rest of a string with an embedded expression.
+                TokenType::Literal(Literal::String | Literal::Interpolation) => break,
+                TokenType::Operator(Operator::Unary(UnaryOperator::LeftParen)) => Op::Call,
+                TokenType::Operator(op) => op
+                    .try_into()
+                    .map_err(|kind| Error::ParseError(ParseError::new(kind, token.span())))?,
+                TokenType::Keyword(kw @ (Keyword::And | Keyword::Or)) => kw
+                    .try_into()
+                    .map_err(|kind| Error::ParseError(ParseError::new(kind, token.span())))?,
+                ty => {
+                    return Err(Error::ParseError(ParseError::new(
+                        ParseErrorKind::UnexpectedToken(ty, token.lexeme().to_string()),
+                        token.span(),
+                    )));
                 }
-                _ => break,
             };
 
             if let Some((l_bp, ())) = op.postfix_binding_power() {
                 if l_bp < min_bp {
                     break;
                 }
-                self.lexer.next();
+                self.advance();
 
-                lhs = TokenTree::Cons(op, vec![lhs]);
+                lhs = match op {
+                    Op::Call => self.parse_call(lhs)?,
+                    _ => {
+                        let span = lhs.span().to(token.span());
+                        TokenTree::Cons(op, vec![lhs], span)
+                    }
+                };
                 continue;
             }
 
@@ -116,11 +511,40 @@ impl<'a> Parser<'a> {
                 if l_bp < min_bp {
                     break;
                 }
-                self.lexer.next();
+                self.advance();
 
-                let rhs = self.parse_expr(r_bp)?;
+                if let Op::Dot = op {
+                    let (name, name_span) = self.expect_identifier()?;
+                    let span = lhs.span().to(name_span);
+                    let name = TokenTree::Atom(Atom::Ident(name, NodeId::fresh()), name_span);
+                    lhs = TokenTree::Cons(op, vec![lhs, name], span);
+                    continue;
+                }
 
-                lhs = TokenTree::Cons(op, vec![lhs, rhs]);
+                if let Op::Ternary = op {
+                    let then_branch = self.parse_expr(0)?;
+                    self.expect(UnaryOperator::Colon)?;
+                    let else_branch = self.parse_expr(r_bp)?;
+                    let span = lhs.span().to(else_branch.span());
+                    lhs = TokenTree::Cons(op, vec![lhs, then_branch, else_branch], span);
+                    continue;
+                }
+
+                if let Op::Equal = op
+                    && !matches!(
+                        lhs,
+                        TokenTree::Atom(Atom::Ident(..), _) | TokenTree::Cons(Op::Dot, _, _)
+                    )
+                {
+                    return Err(Error::ParseError(ParseError::new(
+                        ParseErrorKind::InvalidAssignmentTarget,
+                        token.span(),
+                    )));
+                }
+
+                let rhs = self.parse_expr(r_bp)?;
+                let span = lhs.span().to(rhs.span());
+                lhs = TokenTree::Cons(op, vec![lhs, rhs], span);
                 continue;
             }
 
@@ -129,4 +553,381 @@ impl<'a> Parser<'a> {
 
         Ok(lhs)
     }
+
+    /// Parses the embedded expressions and remaining parts of a string whose
+    /// first part is `head`.
+    fn parse_interpolation(&mut self, head: Token<'a>) -> Result<TokenTree<'a>, Error> {
+        let mut operands = Vec::new();
+        let mut part = head;
+        loop {
+            let text = Token::unescape(part.lexeme());
+            if !text.is_empty() {
+                operands.push(TokenTree::Atom(Atom::String(text), part.span()));
+            }
+            if part.ty() == TokenType::Literal(Literal::String) {
+                break;
+            }
+
+            operands.push(self.parse_expr(0)?);
+            part = match self.advance() {
+                Some(Ok(token))
+                    if matches!(
+                        token.ty(),
+                        TokenType::Literal(Literal::String | Literal::Interpolation)
+                    ) =>
+                {
+                    token
+                }
+                Some(Ok(token)) => {
+                    return Err(Error::LexingError(LexingError::new(
+                        LexingErrorKind::UnexpectedToken {
+                            expected: TokenType::Literal(Literal::String),
+                            found: token.ty(),
+                        },
+                        token.span(),
+                    )));
+                }
+                Some(Err(e)) => return Err(e),
+                None => return Err(Error::UnexpectedEndOfInput(self.lexer.end_of_input())),
+            };
+        }
+
+        Ok(TokenTree::Cons(
+            Op::Interpolate,
+            operands,
+            head.span().to(part.span()),
+        ))
+    }
+
+    /// Parses the arguments of a call to `callee` whose opening parenthesis
+    /// was already consumed.
+    fn parse_call(&mut self, callee: TokenTree<'a>) -> Result<TokenTree<'a>, Error> {
+        let span = callee.span();
+        let mut operands = vec![callee];
+        let right_paren = if let Some(right_paren) = self.consume_token(TokenType::Operator(
+            Operator::Unary(UnaryOperator::RightParen),
+        )) {
+            right_paren
+        } else {
+            loop {
+                if operands.len() > MAX_ARGUMENTS {
+                    return Err(Error::ParseError(ParseError::new(
+                        ParseErrorKind::TooManyArguments,
+                        self.next_span(),
+                    )));
+                }
+                operands.push(self.parse_expr(0)?);
+                if !self.consume(TokenType::Operator(Operator::Unary(UnaryOperator::Comma))) {
+                    break;
+                }
+            }
+            self.expect(UnaryOperator::RightParen)?
+        };
+
+        Ok(TokenTree::Cons(
+            Op::Call,
+            operands,
+            span.to(right_paren.span()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_program(input: &str) -> Result<Program<'_>, Error> {
+        let (program, errors) = Parser::with_lexer(Lexer::new(input)).parse_program();
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(program),
+        }
+    }
+
+    #[test]
+    fn statements() {
+        let program = parse_program("print 1; print 2;").unwrap();
+        assert_eq!(program.to_string(), "(print 1.0)\n(print 2.0)\n");
+
+        let program = parse_program("var a = 1; var b; { a = b; }").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(var a 1.0)\n(var b)\n(block (= a b))\n"
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        let program =
+            parse_program("if (a) print 1; else { print 2; } while (b) b = nil;").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(if a (print 1.0) (block (print 2.0)))\n(while b (= b nil))\n"
+        );
+
+        let program =
+            parse_program("for (var i = 0; i < 3; i = i + 1) print i; for (;;) {}").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(for (var i 0.0) (< i 3.0) (= i (+ i 1.0)) (print i))\n(for nil nil nil (block))\n"
+        );
+    }
+
+    #[test]
+    fn functions_and_classes() {
+        let program =
+            parse_program("fun add(a, b) { return a + b; } class Foo { bar() { return; } }")
+                .unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(fun add (a b) (return (+ a b)))\n(class Foo (fun bar () (return)))\n"
+        );
+    }
+
+    #[test]
+    fn missing_semicolon() {
+        match parse_program("print 1 print 2;") {
+            Err(Error::ParseError(_)) => {}
+            o => panic!("Expected a parse error, got: {:?}", o),
+        }
+        match parse_program("{ print 1; ") {
+            Err(Error::UnexpectedEndOfInput(_)) => {}
+            o => panic!("Expected unexpected end of input, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn assignment() {
+        let program = parse_program("a = b = 1;").unwrap();
+        assert_eq!(program.to_string(), "(= a (= b 1.0))\n");
+
+        match parse_program("a + b = 1;") {
+            Err(Error::ParseError(e)) => {
+                assert!(matches!(e.kind(), ParseErrorKind::InvalidAssignmentTarget));
+            }
+            o => panic!("Expected an invalid assignment target error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn calls() {
+        let program = parse_program("f(); g(1)(2, 3); -h(x);").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(call f)\n(call (call g 1.0) 2.0 3.0)\n(- (call h x))\n"
+        );
+
+        let arguments = vec!["1"; 256].join(", ");
+        match parse_program(&format!("f({arguments});")) {
+            Err(Error::ParseError(e)) => {
+                assert!(matches!(e.kind(), ParseErrorKind::TooManyArguments));
+            }
+            o => panic!("Expected a too many arguments error, got: {:?}", o),
+        }
+
+        let params = (0..256).map(|i| format!("p{i}")).collect::<Vec<_>>();
+        match parse_program(&format!("fun f({}) {{}}", params.join(", "))) {
+            Err(Error::ParseError(e)) => {
+                assert!(matches!(e.kind(), ParseErrorKind::TooManyParameters));
+            }
+            o => panic!("Expected a too many parameters error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn properties() {
+        let program = parse_program("a.b.c = d.e(f).g;").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(= (. (. a b) c) (. (call (. d e) f) g))\n"
+        );
+
+        match parse_program("a.1;") {
+            Err(Error::LexingError(_)) => {}
+            o => panic!(
+                "Expected an error for a non-identifier property, got: {:?}",
+                o
+            ),
+        }
+    }
+
+    #[test]
+    fn inheritance() {
+        let program = parse_program("class B < A { f() { return super.f(); } }").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(class B < A (fun f () (return (call (. super f)))))\n"
+        );
+    }
+
+    #[test]
+    fn logical_operators() {
+        let program = parse_program("a = b or c and d == e; f or g or h;").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(= a (or b (and c (== d e))))\n(or (or f g) h)\n"
+        );
+    }
+
+    #[test]
+    fn ternary() {
+        let program =
+            parse_program("x = a == nil ? b : c ? d : e; f = g or h ? i = 1 : j;").unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(= x (?: (== a nil) b (?: c d e)))\n(= f (?: (or g h) (= i 1.0) j))\n"
+        );
+
+        match parse_program("a ? b;") {
+            Err(Error::LexingError(_)) => {}
+            o => panic!("Expected an error for a missing ':', got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let input = "x = (a + b) * f(c);";
+        let program = parse_program(input).unwrap();
+        let Stmt::Expression(expr) = &program.declarations()[0] else {
+            panic!("Expected an expression statement");
+        };
+        assert_eq!(expr.span(), Span::new(0, 18, 1, 1));
+
+        let TokenTree::Cons(Op::Equal, operands, _) = expr else {
+            panic!("Expected an assignment, got: {}", expr);
+        };
+        let TokenTree::Cons(Op::Star, operands, span) = &operands[1] else {
+            panic!("Expected a multiplication, got: {}", operands[1]);
+        };
+        assert_eq!(&input[span.start()..span.end()], "(a + b) * f(c)");
+        assert_eq!(operands[0].span(), Span::new(4, 11, 1, 5));
+        assert_eq!(operands[1].span(), Span::new(14, 18, 1, 15));
+
+        match parse_program("print 1;\n  1 + ;") {
+            Err(Error::ParseError(e)) => {
+                assert_eq!(e.span(), Span::new(15, 16, 2, 7));
+            }
+            o => panic!("Expected a parse error, got: {:?}", o),
+        }
+        match parse_program("print 1;\nprint a {;") {
+            Err(Error::ParseError(e)) => {
+                assert!(matches!(e.kind(), ParseErrorKind::UnsupportedOperator(_)));
+                assert_eq!(e.span(), Span::new(17, 18, 2, 9));
+            }
+            o => panic!("Expected a parse error, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn error_recovery() {
+        let input = "print 1 print 2;
+            var = 3;
+            print 4;
+            fun f() { print ; return 5; }
+            class { }
+            print 6 +;
+            print 7;";
+        let (program, errors) = Parser::with_lexer(Lexer::new(input)).parse_program();
+        assert_eq!(
+            program.to_string(),
+            "(print 2.0)\n(print 4.0)\n(fun f () (return 5.0))\n(print 7.0)\n"
+        );
+
+        let lines = errors.iter().map(|e| e.span().line()).collect::<Vec<_>>();
+        assert_eq!(lines, vec![1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn lenient() {
+        assert_eq!(
+            Parser::parse_lenient("1 + 2 * x").unwrap().to_string(),
+            "(+ 1.0 (* 2.0 x))\n"
+        );
+        assert_eq!(
+            Parser::parse_lenient("print 1").unwrap().to_string(),
+            "(print 1.0)\n"
+        );
+        assert_eq!(
+            Parser::parse_lenient("print 1; print").unwrap_err().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn error_recovery_at_end_of_input() {
+        let (program, errors) =
+            Parser::with_lexer(Lexer::new("print 1; { print ;")).parse_program();
+        assert_eq!(program.to_string(), "(print 1.0)\n");
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], Error::ParseError(_)));
+        assert!(matches!(errors[1], Error::UnexpectedEndOfInput(_)));
+    }
+
+    #[test]
+    fn interpolation() {
+        let program = parse_program(r#"print "a ${b} c ${d + "e${f}"}";"#).unwrap();
+        assert_eq!(
+            program.to_string(),
+            "(print (interpolate a  b  c  (+ d (interpolate e f))))\n"
+        );
+
+        match parse_program(r#"print "a ${b)}";"#) {
+            Err(Error::LexingError(e)) => assert!(matches!(
+                e.kind(),
+                LexingErrorKind::UnexpectedToken {
+                    expected: TokenType::Literal(Literal::String),
+                    ..
+                }
+            )),
+            o => panic!("Expected an error for an unclosed expression, got: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn json() {
+        let tree = Parser::with_lexer(Lexer::new("-a.b(nil)")).parse().unwrap();
+        let span = |start: usize, end: usize| serde_json::json!({"start": start, "end": end, "line": 1, "column": start + 1});
+        assert_eq!(
+            serde_json::to_value(&tree).unwrap(),
+            serde_json::json!({
+                "op": "-",
+                "operands": [{
+                    "op": "call",
+                    "operands": [
+                        {
+                            "op": ".",
+                            "operands": [
+                                {"atom": "identifier", "value": "a", "span": span(1, 2)},
+                                {"atom": "identifier", "value": "b", "span": span(3, 4)},
+                            ],
+                            "span": span(1, 4),
+                        },
+                        {"atom": "nil", "value": null, "span": span(5, 8)},
+                    ],
+                    "span": span(1, 9),
+                }],
+                "span": span(0, 9),
+            })
+        );
+    }
+
+    #[test]
+    fn program_json() {
+        let program = Parser::parse_lenient("fun f(a) { return a; }").unwrap();
+        let span = |start: usize, end: usize| serde_json::json!({"start": start, "end": end, "line": 1, "column": start + 1});
+        assert_eq!(
+            serde_json::to_value(&program).unwrap(),
+            serde_json::json!([{
+                "stmt": "fun",
+                "name": "f",
+                "params": [{"name": "a", "span": span(6, 7)}],
+                "body": [{
+                    "stmt": "return",
+                    "value": {"atom": "identifier", "value": "a", "span": span(18, 19)},
+                    "span": span(11, 17),
+                }],
+                "span": span(4, 5),
+            }])
+        );
+    }
 }
@@ -0,0 +1,258 @@
+//! The interactive prompt.
+//!
+//! Every input is copied out of its source once parsed, so that functions
+//! declared in one input keep working after it has been dropped.
+
+use std::{io::IsTerminal, path::PathBuf};
+
+use rustyline::{
+    Editor, Helper,
+    completion::Completer,
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::{ValidationContext, ValidationResult, Validator},
+};
+
+use crate::{
+    Lexer, Parser, Resolver,
+    diagnostics::{Diagnostic, Format, Renderer},
+    error::{Error, LexingErrorKind},
+    interpreter::{Interpreter, Value},
+    token::{Literal, Operator, Stmt, TokenType, UnaryOperator},
+};
+
+const HELP: &str = "\
+:tokens <code>  Show the tokens of <code>
+:ast <code>     Show the syntax tree of <code>
+:load <file>    Run <file> in this session
+:help           Show this message";
+
+pub struct Repl {
+    interpreter: Interpreter<'static>,
+    error_format: Format,
+}
+
+impl Repl {
+    pub fn new(error_format: Format) -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            error_format,
+        }
+    }
+
+    /// Reads and runs inputs until the end of input, keeping the history in
+    /// `~/.rslox_history`.
+    pub fn run(mut self) -> rustyline::Result<()> {
+        let mut editor = Editor::<LoxHelper, DefaultHistory>::new()?;
+        editor.set_helper(Some(LoxHelper));
+        let history =
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".rslox_history"));
+        if let Some(history) = &history {
+            // There is no history the first time.
+            let _ = editor.load_history(history);
+        }
+
+        loop {
+            match editor.readline("> ") {
+                Ok(line) => {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    editor.add_history_entry(line.as_str())?;
+                    self.handle(&line);
+                }
+                // Ctrl-C discards the current input.
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(history) = &history {
+            editor.save_history(history)?;
+        }
+        Ok(())
+    }
+
+    /// Runs a meta-command or a piece of code, printing its result.
+    fn handle(&mut self, line: &str) {
+        let Some(command) = line.trim_start().strip_prefix(':') else {
+            self.run_source(line, "<repl>");
+            return;
+        };
+
+        let (command, argument) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        match command {
+            "tokens" => {
+                for token in Lexer::new(argument) {
+                    match token {
+                        Ok(token) => println!("{}", token),
+                        Err(e) => self.report(argument, "<repl>", &[e]),
+                    }
+                }
+            }
+            "ast" => match Parser::parse_lenient(argument) {
+                Ok(program) => print!("{}", program),
+                Err(errors) => self.report(argument, "<repl>", &errors),
+            },
+            "load" => {
+                let path = argument.trim();
+                match std::fs::read_to_string(path) {
+                    Ok(source) => self.run_source(&source, path),
+                    Err(e) => eprintln!("Failed to read {path}: {e}"),
+                }
+            }
+            "help" => println!("{HELP}"),
+            _ => eprintln!("Unknown command :{command}\n{HELP}"),
+        }
+    }
+
+    fn run_source(&mut self, source: &str, path: &str) {
+        match self.eval(source) {
+            Ok(Some(value)) => println!("{}", value),
+            Ok(None) => {}
+            Err(errors) => self.report(source, path, &errors),
+        }
+    }
+
+    /// Runs `source` in the session, returning the value of the expression
+    /// if that is all it is.
+    pub fn eval(&mut self, source: &str) -> Result<Option<Value<'static>>, Vec<Error>> {
+        let program = Parser::parse_lenient(source)?;
+        let locals = Resolver::new().resolve(&program)?;
+        self.interpreter.add_locals(locals);
+
+        if let [Stmt::Expression(expr)] = program.declarations() {
+            self.interpreter.evaluate(expr).map(Some)
+        } else {
+            self.interpreter.interpret(&program).map(|()| None)
+        }
+        .map_err(|e| vec![e])
+    }
+
+    fn report(&self, source: &str, path: &str, errors: &[Error]) {
+        let renderer = Renderer::new(source, path, self.error_format)
+            .with_color(self.error_format != Format::Json && std::io::stderr().is_terminal());
+        for e in errors {
+            eprintln!("{}", renderer.render(&Diagnostic::from(e)));
+        }
+    }
+}
+
+/// Returns `false` if `input` ends inside a string, a comment or unbalanced
+/// parentheses or braces, so that the prompt should read another line.
+pub fn is_complete(input: &str) -> bool {
+    let mut depth = 0isize;
+    for token in Lexer::new(input) {
+        match token {
+            Ok(token) => match token.ty() {
+                TokenType::Operator(Operator::Unary(
+                    UnaryOperator::LeftParen | UnaryOperator::LeftBrace,
+                )) => depth += 1,
+                TokenType::Operator(Operator::Unary(
+                    UnaryOperator::RightParen | UnaryOperator::RightBrace,
+                )) => depth -= 1,
+                // The start and the end of a string with embedded expressions.
+                TokenType::Literal(Literal::Interpolation) if token.lexeme().starts_with('"') => {
+                    depth += 1
+                }
+                TokenType::Literal(Literal::String) if token.lexeme().starts_with('}') => {
+                    depth -= 1
+                }
+                _ => {}
+            },
+            Err(Error::LexingError(e))
+                if matches!(
+                    e.kind(),
+                    LexingErrorKind::UnterminatedString
+                        | LexingErrorKind::UnterminatedComment { .. }
+                ) =>
+            {
+                return false;
+            }
+            Err(_) => {}
+        }
+    }
+    depth <= 0
+}
+
+/// Keeps reading lines while the input is incomplete.
+struct LoxHelper;
+
+impl Validator for LoxHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(if is_complete(ctx.input()) {
+            ValidationResult::Valid(None)
+        } else {
+            ValidationResult::Incomplete
+        })
+    }
+}
+
+impl Completer for LoxHelper {
+    type Candidate = String;
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Helper for LoxHelper {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(repl: &mut Repl, source: &str) -> Option<String> {
+        match repl.eval(source) {
+            Ok(value) => value.map(|value| value.to_string()),
+            Err(errors) => panic!("Failed to run {source}: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn persistent_globals() {
+        let mut repl = Repl::new(Format::Short);
+        assert_eq!(eval(&mut repl, "var a = 1;"), None);
+        assert_eq!(eval(&mut repl, "fun add(b) { return a + b; }"), None);
+        assert_eq!(eval(&mut repl, "a = 2;").as_deref(), Some("2"));
+        assert_eq!(eval(&mut repl, "add(3)").as_deref(), Some("5"));
+        assert_eq!(eval(&mut repl, "\"a\" + \"b\"").as_deref(), Some("ab"));
+        assert!(repl.eval("undefined").is_err());
+        assert!(repl.eval("1 +").is_err());
+    }
+
+    #[test]
+    fn closures_outlive_their_input() {
+        let mut repl = Repl::new(Format::Short);
+        eval(
+            &mut repl,
+            "fun counter() { var n = 0; fun inc() { n = n + 1; return n; } return inc; }",
+        );
+        eval(&mut repl, "var count = counter();");
+        for i in 1..=100 {
+            // Fresh inputs must not pick up the scope distances of dropped ones.
+            eval(&mut repl, &format!("var x{i} = {i}; {{ var y = x{i}; }}"));
+            assert_eq!(eval(&mut repl, "count()"), Some(i.to_string()));
+        }
+    }
+
+    #[test]
+    fn incomplete_input() {
+        assert!(is_complete("print 1;"));
+        assert!(is_complete("fun f() { return (1); }"));
+        assert!(is_complete("print \"a ${b} c\";"));
+        assert!(is_complete("}"));
+        assert!(!is_complete("fun f() {"));
+        assert!(!is_complete("fun f() { if (a"));
+        assert!(!is_complete("print \"a"));
+        assert!(!is_complete("print \"a ${"));
+        assert!(!is_complete("/* a"));
+    }
+}
@@ -0,0 +1,423 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    error::{Error, ParseError, ParseErrorKind},
+    token::{Atom, Function, NodeId, Op, Program, Span, Stmt, TokenTree},
+};
+
+/// The scope distance of every local variable reference in a program.
+///
+/// References are keyed by the [`NodeId`] of their atom, so the distances stay
+/// valid however the program is moved, and can be kept for functions that
+/// outlive it. References that are not recorded are globals.
+#[derive(Debug, Default)]
+pub struct Locals {
+    depths: HashMap<NodeId, usize>,
+}
+
+impl Locals {
+    /// Returns how many scopes away from the innermost one `node` is declared.
+    pub fn depth(&self, node: &TokenTree<'_>) -> Option<usize> {
+        self.depths.get(&Self::key(node)?).copied()
+    }
+
+    pub fn extend(&mut self, other: Locals) {
+        self.depths.extend(other.depths);
+    }
+
+    fn insert(&mut self, node: &TokenTree<'_>, depth: usize) {
+        if let Some(key) = Self::key(node) {
+            self.depths.insert(key, depth);
+        }
+    }
+
+    fn key(node: &TokenTree<'_>) -> Option<NodeId> {
+        match node {
+            TokenTree::Atom(Atom::Ident(_, id) | Atom::This(id) | Atom::Super(id), _) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionKind {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+}
+
+/// Static analysis pass that binds every variable reference to the scope it
+/// refers to and reports the errors that can be caught before running.
+pub struct Resolver<'a> {
+    /// One map per nested local scope, from a name to whether its
+    /// initializer has finished.
+    scopes: Vec<HashMap<Cow<'a, str>, bool>>,
+    locals: Locals,
+    current_function: FunctionKind,
+    current_class: ClassKind,
+    errors: Vec<Error>,
+}
+
+impl Default for Resolver<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            locals: Locals::default(),
+            current_function: FunctionKind::None,
+            current_class: ClassKind::None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Resolves every declaration of `program`, reporting all errors found.
+    pub fn resolve(mut self, program: &Program<'a>) -> Result<Locals, Vec<Error>> {
+        self.resolve_stmts(program.declarations());
+        if self.errors.is_empty() {
+            Ok(self.locals)
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn resolve_stmts(&mut self, stmts: &[Stmt<'a>]) {
+        for stmt in stmts {
+            self.resolve_stmt(stmt);
+        }
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Stmt<'a>) {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) => self.resolve_expr(expr),
+            Stmt::Var {
+                name,
+                initializer,
+                span,
+            } => {
+                self.declare(name.clone(), *span);
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer);
+                }
+                self.define(name.clone());
+            }
+            Stmt::Block(stmts) => {
+                self.begin_scope();
+                self.resolve_stmts(stmts);
+                self.end_scope();
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_stmt(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expr(condition);
+                self.resolve_stmt(body);
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                // The interpreter runs the whole loop in its own scope.
+                self.begin_scope();
+                if let Some(initializer) = initializer {
+                    self.resolve_stmt(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.resolve_expr(condition);
+                }
+                if let Some(increment) = increment {
+                    self.resolve_expr(increment);
+                }
+                self.resolve_stmt(body);
+                self.end_scope();
+            }
+            Stmt::Function(function) => {
+                self.declare(function.name.clone(), function.span);
+                self.define(function.name.clone());
+                self.resolve_function(function, FunctionKind::Function);
+            }
+            Stmt::Return { value, span } => {
+                if self.current_function == FunctionKind::None {
+                    self.error(ParseErrorKind::ReturnAtTopLevel, *span);
+                }
+                if let Some(value) = value {
+                    if self.current_function == FunctionKind::Initializer {
+                        self.error(ParseErrorKind::ReturnFromInitializer, *span);
+                    }
+                    self.resolve_expr(value);
+                }
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                span,
+            } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassKind::Class;
+
+                self.declare(name.clone(), *span);
+                self.define(name.clone());
+
+                if let Some(superclass) = superclass {
+                    if let TokenTree::Atom(Atom::Ident(superclass_name, _), span) = superclass
+                        && superclass_name == name
+                    {
+                        self.error(ParseErrorKind::InheritFromSelf(name.to_string()), *span);
+                    }
+                    self.current_class = ClassKind::Subclass;
+                    self.resolve_expr(superclass);
+
+                    self.begin_scope();
+                    self.define(Cow::Borrowed("super"));
+                }
+
+                self.begin_scope();
+                self.define(Cow::Borrowed("this"));
+                for method in methods {
+                    let kind = if method.name == "init" {
+                        FunctionKind::Initializer
+                    } else {
+                        FunctionKind::Method
+                    };
+                    self.resolve_function(method, kind);
+                }
+                self.end_scope();
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+                self.current_class = enclosing_class;
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, function: &Function<'a>, kind: FunctionKind) {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+
+        self.begin_scope();
+        for (param, span) in &function.params {
+            self.declare(param.clone(), *span);
+            self.define(param.clone());
+        }
+        self.resolve_stmts(&function.body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_expr(&mut self, expr: &TokenTree<'a>) {
+        match expr {
+            TokenTree::Atom(Atom::Ident(name, _), span) => {
+                if let Some(false) = self.scopes.last().and_then(|scope| scope.get(name)) {
+                    self.error(
+                        ParseErrorKind::ReadInOwnInitializer(name.to_string()),
+                        *span,
+                    );
+                }
+                self.resolve_local(expr, name);
+            }
+            TokenTree::Atom(Atom::This(_), span) => {
+                if self.current_class == ClassKind::None {
+                    self.error(ParseErrorKind::ThisOutsideClass, *span);
+                    return;
+                }
+                self.resolve_local(expr, "this");
+            }
+            TokenTree::Atom(Atom::Super(_), span) => match self.current_class {
+                ClassKind::None => self.error(ParseErrorKind::SuperOutsideClass, *span),
+                ClassKind::Class => self.error(ParseErrorKind::SuperWithoutSuperclass, *span),
+                ClassKind::Subclass => self.resolve_local(expr, "super"),
+            },
+            TokenTree::Atom(..) => {}
+            TokenTree::Cons(Op::Dot, operands, _) => {
+                // The property name is not a variable reference.
+                self.resolve_expr(&operands[0]);
+            }
+            TokenTree::Cons(_, operands, _) => {
+                for operand in operands {
+                    self.resolve_expr(operand);
+                }
+            }
+        }
+    }
+
+    fn resolve_local(&mut self, expr: &TokenTree<'a>, name: &str) {
+        if let Some(depth) = self
+            .scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name))
+        {
+            self.locals.insert(expr, depth);
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: Cow<'a, str>, span: Span) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if scope.insert(name.clone(), false).is_some() {
+            self.error(ParseErrorKind::Redeclaration(name.into_owned()), span);
+        }
+    }
+
+    fn define(&mut self, name: Cow<'a, str>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, true);
+        }
+    }
+
+    fn error(&mut self, kind: ParseErrorKind, span: Span) {
+        self.errors
+            .push(Error::ParseError(ParseError::new(kind, span)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Lexer, Parser};
+
+    use super::*;
+
+    fn resolve(input: &str) -> Result<Locals, Vec<Error>> {
+        let mut parser = Parser::with_lexer(Lexer::new(input));
+        let (program, errors) = parser.parse_program();
+        assert!(errors.is_empty(), "Failed to parse program: {:?}", errors);
+        Resolver::new().resolve(&program)
+    }
+
+    fn errors(input: &str) -> Vec<ParseErrorKind> {
+        match resolve(input) {
+            Ok(_) => panic!("Expected resolution errors for: {input}"),
+            Err(errors) => errors
+                .into_iter()
+                .map(|e| match e {
+                    Error::ParseError(e) => e.kind().clone(),
+                    e => panic!("Expected a parse error, got: {:?}", e),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn valid_programs() {
+        assert!(resolve("var a = 1; var a = 2; { var b = a; } fun f(x) { return x; }").is_ok());
+        assert!(resolve("class A { init() { return; } f() { return this; } }").is_ok());
+        assert!(resolve("class A {} class B < A { f() { return super.f; } }").is_ok());
+    }
+
+    #[test]
+    fn depths() {
+        let input = "{ var a = 1; { fun f() { return a; } } }";
+        let mut parser = Parser::with_lexer(Lexer::new(input));
+        let (program, _) = parser.parse_program();
+        let locals = Resolver::new().resolve(&program).unwrap();
+
+        let Stmt::Block(outer) = &program.declarations()[0] else {
+            panic!("Expected a block");
+        };
+        let Stmt::Block(inner) = &outer[1] else {
+            panic!("Expected a block");
+        };
+        let Stmt::Function(function) = &inner[0] else {
+            panic!("Expected a function");
+        };
+        let Stmt::Return {
+            value: Some(value), ..
+        } = &function.body[0]
+        else {
+            panic!("Expected a return statement");
+        };
+        // function scope -> inner block -> outer block
+        assert_eq!(locals.depth(value), Some(2));
+    }
+
+    #[test]
+    fn static_errors() {
+        assert!(matches!(
+            errors("{ var a = a; }").as_slice(),
+            [ParseErrorKind::ReadInOwnInitializer(_)]
+        ));
+        assert!(matches!(
+            errors("fun f(a) { var a; }").as_slice(),
+            [ParseErrorKind::Redeclaration(_)]
+        ));
+        assert!(matches!(
+            errors("return 1;").as_slice(),
+            [ParseErrorKind::ReturnAtTopLevel]
+        ));
+        assert!(matches!(
+            errors("print this;").as_slice(),
+            [ParseErrorKind::ThisOutsideClass]
+        ));
+        assert!(matches!(
+            errors("class A { init() { return 1; } }").as_slice(),
+            [ParseErrorKind::ReturnFromInitializer]
+        ));
+    }
+
+    #[test]
+    fn duplicate_parameters() {
+        let input = "fun f(a, b, a) {}";
+        match resolve(input).unwrap_err().as_slice() {
+            [Error::ParseError(e)] => {
+                assert!(matches!(e.kind(), ParseErrorKind::Redeclaration(_)));
+                assert_eq!(e.span().start(), input.rfind('a').unwrap());
+            }
+            errors => panic!("Expected a redeclaration, got: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn class_errors() {
+        assert!(matches!(
+            errors("class A < A {}").as_slice(),
+            [ParseErrorKind::InheritFromSelf(_)]
+        ));
+        assert!(matches!(
+            errors("fun f() { super.f(); }").as_slice(),
+            [ParseErrorKind::SuperOutsideClass]
+        ));
+        assert!(matches!(
+            errors("class A { f() { super.f(); } }").as_slice(),
+            [ParseErrorKind::SuperWithoutSuperclass]
+        ));
+    }
+
+    #[test]
+    fn reports_every_error() {
+        assert_eq!(errors("return; print this; { var a; var a; }").len(), 3);
+    }
+}
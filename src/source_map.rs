@@ -0,0 +1,128 @@
+use std::cell::Cell;
+
+use crate::token::Span;
+
+/// An index of where each line of a source file starts, for turning byte
+/// offsets into lines and columns without rescanning the source.
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+    source: &'a str,
+    /// The byte offset of the start of each line. The first is always 0.
+    line_starts: Vec<usize>,
+    /// The offset and column of the last `column` lookup. Lookups further
+    /// along the same line count on from it instead of from the line start,
+    /// so lexing a long line stays linear.
+    last_column: Cell<(usize, usize)>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+            last_column: Cell::new((0, 1)),
+        }
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The 1-based line the byte at `offset` is on. Offsets past the end are
+    /// on the last line.
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    /// The 1-based column, in characters, of the byte at `offset`.
+    pub fn column(&self, offset: usize) -> usize {
+        let offset = offset.min(self.source.len());
+        let start = self.line_starts[self.line(offset) - 1];
+        let (from, column) = match self.last_column.get() {
+            (last, column) if (start..=offset).contains(&last) => (last, column),
+            _ => (start, 1),
+        };
+        let column = column + self.source[from..offset].chars().count();
+        self.last_column.set((offset, column));
+        column
+    }
+
+    /// The span of the bytes `start..end`.
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(start, end, self.line(start), self.column(start))
+    }
+
+    /// The byte range of the 1-based `line`, without its line ending.
+    pub fn line_range(&self, line: usize) -> Option<std::ops::Range<usize>> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |&next| next - 1);
+        let text = &self.source[start..end];
+        Some(start..start + text.strip_suffix('\r').unwrap_or(text).len())
+    }
+
+    /// The text of the 1-based `line`, without its line ending.
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        self.line_range(line).map(|range| &self.source[range])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lines_and_columns() {
+        let source = "var a;\r\nprint \"é\";\n\nb";
+        let map = SourceMap::new(source);
+        assert_eq!(map.line_count(), 4);
+
+        assert_eq!(map.line(0), 1);
+        assert_eq!(map.line(7), 1); // The '\n' ends line 1.
+        assert_eq!(map.line(8), 2);
+        assert_eq!(map.line(source.len()), 4);
+        assert_eq!(map.line(source.len() + 10), 4);
+
+        // `é` is two bytes but one column.
+        let semicolon = source.find("\";").unwrap() + 1;
+        assert_eq!(map.column(semicolon), 10);
+        assert_eq!(map.span(semicolon, semicolon + 1), Span::new(18, 19, 2, 10));
+
+        assert_eq!(map.line_text(1), Some("var a;"));
+        assert_eq!(map.line_text(2), Some("print \"é\";"));
+        assert_eq!(map.line_text(3), Some(""));
+        assert_eq!(map.line_text(4), Some("b"));
+        assert_eq!(map.line_text(0), None);
+        assert_eq!(map.line_text(5), None);
+    }
+
+    #[test]
+    fn long_line() {
+        let source = "var é = 1; ".repeat(10_000);
+        let map = SourceMap::new(&source);
+        let statement = "var é = 1; ".len();
+        // Forwards along the line, as the lexer looks them up, then backwards.
+        let offsets = (0..10_000).chain((0..10_000).rev().step_by(7));
+        for i in offsets {
+            assert_eq!(map.column(i * statement), i * 11 + 1);
+            assert_eq!(map.column(i * statement + 8), i * 11 + 8);
+        }
+    }
+
+    #[test]
+    fn empty_source() {
+        let map = SourceMap::new("");
+        assert_eq!(map.line_count(), 1);
+        assert_eq!(map.span(0, 0), Span::new(0, 0, 1, 1));
+        assert_eq!(map.line_text(1), Some(""));
+    }
+}
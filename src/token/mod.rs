@@ -1,7 +1,12 @@
 mod operator;
+mod span;
+mod stmt;
+#[allow(clippy::module_inception)]
 mod token;
 mod tree;
 
 pub use operator::*;
+pub use span::*;
+pub use stmt::*;
 pub use token::*;
 pub use tree::*;
@@ -1,4 +1,4 @@
-use crate::error::{Error, LexingError, LexingErrorKind};
+use crate::error::LexingErrorKind;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum UnaryOperator {
@@ -14,6 +14,8 @@ pub enum UnaryOperator {
     Star,
     Slash,
     Bang,
+    Question,
+    Colon,
 }
 
 impl std::fmt::Display for UnaryOperator {
@@ -31,6 +33,8 @@ impl std::fmt::Display for UnaryOperator {
             Self::Star => write!(f, "STAR"),
             Self::Slash => write!(f, "SLASH"),
             Self::Bang => write!(f, "BANG"),
+            Self::Question => write!(f, "QUESTION"),
+            Self::Colon => write!(f, "COLON"),
         }
     }
 }
@@ -76,14 +80,14 @@ impl std::fmt::Display for Operator {
 }
 
 impl TryFrom<char> for Operator {
-    type Error = Error;
+    type Error = LexingErrorKind;
     fn try_from(value: char) -> Result<Self, Self::Error> {
         Self::try_from(value.to_string().as_str())
     }
 }
 
 impl TryFrom<&str> for Operator {
-    type Error = Error;
+    type Error = LexingErrorKind;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "(" => Ok(Operator::Unary(UnaryOperator::LeftParen)),
@@ -98,6 +102,8 @@ impl TryFrom<&str> for Operator {
             "*" => Ok(Operator::Unary(UnaryOperator::Star)),
             "/" => Ok(Operator::Unary(UnaryOperator::Slash)),
             "!" => Ok(Operator::Unary(UnaryOperator::Bang)),
+            "?" => Ok(Operator::Unary(UnaryOperator::Question)),
+            ":" => Ok(Operator::Unary(UnaryOperator::Colon)),
             "!=" => Ok(Operator::Binary(BinaryOperator::BangEqual)),
             "<" => Ok(Operator::Binary(BinaryOperator::Less)),
             "<=" => Ok(Operator::Binary(BinaryOperator::LessEqual)),
@@ -105,9 +111,7 @@ impl TryFrom<&str> for Operator {
             "==" => Ok(Operator::Binary(BinaryOperator::EqualEqual)),
             ">" => Ok(Operator::Binary(BinaryOperator::Greater)),
             ">=" => Ok(Operator::Binary(BinaryOperator::GreaterEqual)),
-            _ => Err(Error::LexingError(LexingError::new(
-                LexingErrorKind::InvalidOperator(value.to_string()),
-            ))),
+            _ => Err(LexingErrorKind::InvalidOperator(value.to_string())),
         }
     }
 }
@@ -0,0 +1,377 @@
+use std::{borrow::Cow, rc::Rc};
+
+use serde::{Serialize, ser::SerializeStruct};
+
+use super::{Span, TokenTree};
+
+/// A whole Lox program: the list of top-level declarations in source order.
+#[derive(Debug, Clone, Default)]
+pub struct Program<'a> {
+    declarations: Vec<Stmt<'a>>,
+}
+
+impl<'a> Program<'a> {
+    pub fn new(declarations: Vec<Stmt<'a>>) -> Self {
+        Self { declarations }
+    }
+
+    pub fn declarations(&self) -> &[Stmt<'a>] {
+        &self.declarations
+    }
+
+    /// Copies every borrowed string, so the program no longer refers to the
+    /// source and can outlive it.
+    pub fn into_owned(self) -> Program<'static> {
+        Program::new(
+            self.declarations
+                .into_iter()
+                .map(Stmt::into_owned)
+                .collect(),
+        )
+    }
+}
+
+impl std::fmt::Display for Program<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for stmt in &self.declarations {
+            writeln!(f, "{}", stmt)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes as the list of its declarations.
+impl Serialize for Program<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.declarations.serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Stmt<'a> {
+    Expression(TokenTree<'a>),
+    Print(TokenTree<'a>),
+    Var {
+        name: Cow<'a, str>,
+        initializer: Option<TokenTree<'a>>,
+        /// The span of the variable's name.
+        span: Span,
+    },
+    Block(Vec<Stmt<'a>>),
+    If {
+        condition: TokenTree<'a>,
+        then_branch: Box<Stmt<'a>>,
+        else_branch: Option<Box<Stmt<'a>>>,
+    },
+    While {
+        condition: TokenTree<'a>,
+        body: Box<Stmt<'a>>,
+    },
+    For {
+        initializer: Option<Box<Stmt<'a>>>,
+        condition: Option<TokenTree<'a>>,
+        increment: Option<TokenTree<'a>>,
+        body: Box<Stmt<'a>>,
+    },
+    Function(Rc<Function<'a>>),
+    Return {
+        value: Option<TokenTree<'a>>,
+        /// The span of the `return` keyword.
+        span: Span,
+    },
+    Class {
+        name: Cow<'a, str>,
+        superclass: Option<TokenTree<'a>>,
+        methods: Vec<Rc<Function<'a>>>,
+        /// The span of the class's name.
+        span: Span,
+    },
+}
+
+impl Stmt<'_> {
+    pub fn into_owned(self) -> Stmt<'static> {
+        let boxed = |stmt: Box<Stmt<'_>>| Box::new(stmt.into_owned());
+        match self {
+            Stmt::Expression(expr) => Stmt::Expression(expr.into_owned()),
+            Stmt::Print(expr) => Stmt::Print(expr.into_owned()),
+            Stmt::Var {
+                name,
+                initializer,
+                span,
+            } => Stmt::Var {
+                name: owned(name),
+                initializer: initializer.map(TokenTree::into_owned),
+                span,
+            },
+            Stmt::Block(stmts) => Stmt::Block(stmts.into_iter().map(Stmt::into_owned).collect()),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => Stmt::If {
+                condition: condition.into_owned(),
+                then_branch: boxed(then_branch),
+                else_branch: else_branch.map(boxed),
+            },
+            Stmt::While { condition, body } => Stmt::While {
+                condition: condition.into_owned(),
+                body: boxed(body),
+            },
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => Stmt::For {
+                initializer: initializer.map(boxed),
+                condition: condition.map(TokenTree::into_owned),
+                increment: increment.map(TokenTree::into_owned),
+                body: boxed(body),
+            },
+            Stmt::Function(function) => Stmt::Function(function.into_owned_rc()),
+            Stmt::Return { value, span } => Stmt::Return {
+                value: value.map(TokenTree::into_owned),
+                span,
+            },
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                span,
+            } => Stmt::Class {
+                name: owned(name),
+                superclass: superclass.map(TokenTree::into_owned),
+                methods: methods.into_iter().map(Function::into_owned_rc).collect(),
+                span,
+            },
+        }
+    }
+}
+
+fn owned(s: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
+
+/// A function declaration, shared between `fun` statements and class methods.
+#[derive(Debug, Clone)]
+pub struct Function<'a> {
+    pub name: Cow<'a, str>,
+    /// Each parameter's name along with the span of the name.
+    pub params: Vec<(Cow<'a, str>, Span)>,
+    pub body: Vec<Stmt<'a>>,
+    /// The span of the function's name.
+    pub span: Span,
+}
+
+impl Function<'_> {
+    fn into_owned_rc(self: Rc<Self>) -> Rc<Function<'static>> {
+        let function = Rc::unwrap_or_clone(self);
+        Rc::new(Function {
+            name: owned(function.name),
+            params: function
+                .params
+                .into_iter()
+                .map(|(param, span)| (owned(param), span))
+                .collect(),
+            body: function.body.into_iter().map(Stmt::into_owned).collect(),
+            span: function.span,
+        })
+    }
+}
+
+/// Serializes as a `{"stmt": "fun", "name", "params", "body", "span"}` object,
+/// with each parameter as a `{"name", "span"}` object.
+impl Serialize for Function<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut function = serializer.serialize_struct("Function", 5)?;
+        function.serialize_field("stmt", "fun")?;
+        function.serialize_field("name", &self.name)?;
+        let params = self
+            .params
+            .iter()
+            .map(|(name, span)| Param { name, span })
+            .collect::<Vec<_>>();
+        function.serialize_field("params", &params)?;
+        function.serialize_field("body", &self.body)?;
+        function.serialize_field("span", &self.span)?;
+        function.end()
+    }
+}
+
+#[derive(Serialize)]
+struct Param<'p> {
+    name: &'p str,
+    span: &'p Span,
+}
+
+impl std::fmt::Display for Function<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = self
+            .params
+            .iter()
+            .map(|(param, _)| param.as_ref())
+            .collect::<Vec<_>>();
+        write!(f, "(fun {} ({})", self.name, params.join(" "))?;
+        for stmt in &self.body {
+            write!(f, " {}", stmt)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl std::fmt::Display for Stmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stmt::Expression(expr) => write!(f, "{}", expr),
+            Stmt::Print(expr) => write!(f, "(print {})", expr),
+            Stmt::Var {
+                name, initializer, ..
+            } => match initializer {
+                Some(init) => write!(f, "(var {} {})", name, init),
+                None => write!(f, "(var {})", name),
+            },
+            Stmt::Block(stmts) => {
+                write!(f, "(block")?;
+                for stmt in stmts {
+                    write!(f, " {}", stmt)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => match else_branch {
+                Some(else_branch) => {
+                    write!(f, "(if {} {} {})", condition, then_branch, else_branch)
+                }
+                None => write!(f, "(if {} {})", condition, then_branch),
+            },
+            Stmt::While { condition, body } => write!(f, "(while {} {})", condition, body),
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                write!(f, "(for")?;
+                match initializer {
+                    Some(init) => write!(f, " {}", init)?,
+                    None => write!(f, " nil")?,
+                }
+                match condition {
+                    Some(cond) => write!(f, " {}", cond)?,
+                    None => write!(f, " nil")?,
+                }
+                match increment {
+                    Some(incr) => write!(f, " {}", incr)?,
+                    None => write!(f, " nil")?,
+                }
+                write!(f, " {})", body)
+            }
+            Stmt::Function(fun) => write!(f, "{}", fun),
+            Stmt::Return { value, .. } => match value {
+                Some(value) => write!(f, "(return {})", value),
+                None => write!(f, "(return)"),
+            },
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                write!(f, "(class {}", name)?;
+                if let Some(superclass) = superclass {
+                    write!(f, " < {}", superclass)?;
+                }
+                for method in methods {
+                    write!(f, " {}", method)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Serializes statements as objects whose `"stmt"` field names the kind of
+/// statement, with the expressions and statements they contain nested.
+impl Serialize for Stmt<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Stmt::Function(function) = self {
+            return function.serialize(serializer);
+        }
+
+        let mut stmt = serializer.serialize_struct("Stmt", 5)?;
+        match self {
+            Stmt::Expression(expr) => {
+                stmt.serialize_field("stmt", "expression")?;
+                stmt.serialize_field("expr", expr)?;
+            }
+            Stmt::Print(expr) => {
+                stmt.serialize_field("stmt", "print")?;
+                stmt.serialize_field("expr", expr)?;
+            }
+            Stmt::Var {
+                name,
+                initializer,
+                span,
+            } => {
+                stmt.serialize_field("stmt", "var")?;
+                stmt.serialize_field("name", name)?;
+                stmt.serialize_field("initializer", initializer)?;
+                stmt.serialize_field("span", span)?;
+            }
+            Stmt::Block(stmts) => {
+                stmt.serialize_field("stmt", "block")?;
+                stmt.serialize_field("body", stmts)?;
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                stmt.serialize_field("stmt", "if")?;
+                stmt.serialize_field("condition", condition)?;
+                stmt.serialize_field("then", then_branch)?;
+                stmt.serialize_field("else", else_branch)?;
+            }
+            Stmt::While { condition, body } => {
+                stmt.serialize_field("stmt", "while")?;
+                stmt.serialize_field("condition", condition)?;
+                stmt.serialize_field("body", body)?;
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                stmt.serialize_field("stmt", "for")?;
+                stmt.serialize_field("initializer", initializer)?;
+                stmt.serialize_field("condition", condition)?;
+                stmt.serialize_field("increment", increment)?;
+                stmt.serialize_field("body", body)?;
+            }
+            Stmt::Function(_) => unreachable!("functions are serialized above"),
+            Stmt::Return { value, span } => {
+                stmt.serialize_field("stmt", "return")?;
+                stmt.serialize_field("value", value)?;
+                stmt.serialize_field("span", span)?;
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                span,
+            } => {
+                stmt.serialize_field("stmt", "class")?;
+                stmt.serialize_field("name", name)?;
+                stmt.serialize_field("superclass", superclass)?;
+                let methods = methods.iter().map(Rc::as_ref).collect::<Vec<_>>();
+                stmt.serialize_field("methods", &methods)?;
+                stmt.serialize_field("span", span)?;
+            }
+        }
+        stmt.end()
+    }
+}
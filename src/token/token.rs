@@ -1,42 +1,92 @@
 use std::borrow::Cow;
 
-use crate::error::{Error, LexingError, LexingErrorKind};
+use serde::{Serialize, ser::SerializeStruct};
+use unicode_ident::{is_xid_continue, is_xid_start};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
 
-use super::operator::*;
+use crate::error::LexingErrorKind;
+
+use super::{Span, operator::*};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Literal {
     Identifier,
     String,
+    /// The part of a string up to an embedded `${` expression, or between two
+    /// of them. The part after the last one is a [`Literal::String`].
+    Interpolation,
     Number(f64),
 }
 
 impl TryFrom<&str> for Literal {
-    type Error = Error;
+    type Error = LexingErrorKind;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         if value.starts_with('"') {
             if value.ends_with('"') {
                 Ok(Literal::String)
             } else {
-                return Err(Error::LexingError(LexingError::new(
-                    LexingErrorKind::UnterminatedString,
-                )));
+                Err(LexingErrorKind::UnterminatedString)
             }
-        } else if value.chars().all(|c| c.is_ascii_digit() || c == '.') {
-            Ok(Literal::Number(value.parse::<f64>().unwrap()))
+        } else if value.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_number(value)
+                .map(Literal::Number)
+                .ok_or_else(|| LexingErrorKind::InvalidNumber(value.to_string()))
         } else {
-            let starts_with_number = value.chars().next().map_or(false, |c| c.is_ascii_digit());
-
-            if !starts_with_number && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let mut chars = value.chars();
+            if chars.next().is_some_and(|c| is_xid_start(c) || c == '_')
+                && chars.all(is_xid_continue)
+            {
                 return Ok(Literal::Identifier);
             }
 
-            Err(Error::LexingError(LexingError::new(
-                LexingErrorKind::InvalidLiteral(value.to_string()),
-            )))
+            Err(LexingErrorKind::InvalidLiteral(value.to_string()))
+        }
+    }
+}
+
+/// Parses a number literal: decimal with an optional fraction and exponent,
+/// such as `6.02e23`, or an integer with a `0x`, `0b` or `0o` prefix. Digits
+/// may be separated by underscores, as in `1_000_000`.
+fn parse_number(lexeme: &str) -> Option<f64> {
+    // A run of digits that starts with a digit and doesn't end with `_`.
+    let is_digits = |s: &str, radix: u32| {
+        s.starts_with(|c: char| c.is_digit(radix))
+            && !s.ends_with('_')
+            && s.chars().all(|c| c.is_digit(radix) || c == '_')
+    };
+
+    let radix = match lexeme.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0b" | "0B") => 2,
+        Some("0o" | "0O") => 8,
+        _ => 10,
+    };
+    if radix != 10 {
+        let digits = &lexeme[2..];
+        if !is_digits(digits, radix) {
+            return None;
         }
+        let n = u64::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+        return Some(n as f64);
     }
+
+    let (mantissa, exponent) = match lexeme.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (lexeme, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let exponent = exponent.map(|e| e.strip_prefix(['+', '-']).unwrap_or(e));
+    if !is_digits(integer, 10)
+        || fraction.is_some_and(|f| !is_digits(f, 10))
+        || exponent.is_some_and(|e| !is_digits(e, 10))
+    {
+        return None;
+    }
+    lexeme.replace('_', "").parse().ok()
 }
 
 impl std::fmt::Display for Literal {
@@ -44,6 +94,7 @@ impl std::fmt::Display for Literal {
         match self {
             Self::Identifier => write!(f, "IDENTIFIER"),
             Self::String => write!(f, "STRING"),
+            Self::Interpolation => write!(f, "INTERPOLATION"),
             Self::Number(_) => write!(f, "NUMBER"),
         }
     }
@@ -100,7 +151,7 @@ impl std::fmt::Display for Keyword {
 }
 
 impl TryFrom<&str> for Keyword {
-    type Error = Error;
+    type Error = LexingErrorKind;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
@@ -120,9 +171,7 @@ impl TryFrom<&str> for Keyword {
             "true" => Ok(Keyword::True),
             "var" => Ok(Keyword::Var),
             "while" => Ok(Keyword::While),
-            _ => Err(Error::LexingError(LexingError::new(
-                LexingErrorKind::InvalidKeyword(value.to_string()),
-            ))),
+            _ => Err(LexingErrorKind::InvalidKeyword(value.to_string())),
         }
     }
 }
@@ -132,6 +181,8 @@ pub enum TokenType {
     Keyword(Keyword),
     Literal(Literal),
     Operator(Operator),
+    /// A `//` or `/* */` comment, only produced on request.
+    Comment,
     Invalid,
 }
 
@@ -141,6 +192,7 @@ impl std::fmt::Display for TokenType {
             Self::Operator(op) => write!(f, "{}", op),
             Self::Keyword(kw) => write!(f, "{}", kw),
             Self::Literal(lit) => write!(f, "{}", lit),
+            Self::Comment => write!(f, "COMMENT"),
             Self::Invalid => write!(f, "Invalid"),
         }
     }
@@ -164,6 +216,7 @@ impl From<&str> for TokenType {
 pub struct Token<'a> {
     ty: TokenType,
     lexeme: &'a str,
+    span: Span,
 }
 
 impl std::fmt::Display for Token<'_> {
@@ -172,12 +225,12 @@ impl std::fmt::Display for Token<'_> {
         match &self.ty {
             TokenType::Literal(lit) => match lit {
                 Literal::Identifier => write!(f, "{} {} null", self.ty, self.lexeme),
-                Literal::String => write!(
+                Literal::String | Literal::Interpolation => write!(
                     f,
                     "{} {} {}",
                     self.ty,
                     self.lexeme,
-                    self.lexeme.trim_matches('"')
+                    Token::unescape(self.lexeme)
                 ),
                 Literal::Number(num) => write!(
                     f,
@@ -196,9 +249,28 @@ impl std::fmt::Display for Token<'_> {
     }
 }
 
+/// Serializes as an object with the token's type, lexeme, decoded literal
+/// value (`null` if it isn't a literal) and span.
+impl Serialize for Token<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut token = serializer.serialize_struct("Token", 4)?;
+        token.serialize_field("type", &self.ty.to_string())?;
+        token.serialize_field("lexeme", self.lexeme)?;
+        match self.ty {
+            TokenType::Literal(Literal::String | Literal::Interpolation) => {
+                token.serialize_field("value", &Token::unescape(self.lexeme))?
+            }
+            TokenType::Literal(Literal::Number(num)) => token.serialize_field("value", &num)?,
+            _ => token.serialize_field("value", &())?,
+        }
+        token.serialize_field("span", &self.span)?;
+        token.end()
+    }
+}
+
 impl<'a> Token<'a> {
-    pub fn new(ty: TokenType, lexeme: &'a str) -> Self {
-        Self { ty, lexeme }
+    pub fn new(ty: TokenType, lexeme: &'a str, span: Span) -> Self {
+        Self { ty, lexeme, span }
     }
 
     pub fn ty(&self) -> TokenType {
@@ -209,7 +281,75 @@ impl<'a> Token<'a> {
         self.lexeme
     }
 
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Puts an identifier in Unicode Normalization Form C, so identifiers that
+    /// look the same compare equal. Only allocates if it wasn't already.
+    pub fn normalize(ident: &'a str) -> Cow<'a, str> {
+        if is_nfc(ident) {
+            Cow::Borrowed(ident)
+        } else {
+            Cow::Owned(ident.nfc().collect())
+        }
+    }
+
+    /// Strips the delimiters off a string lexeme, or a part of an interpolated
+    /// one, and decodes its escape sequences.
+    ///
+    /// Only allocates when there is an escape sequence to decode. Invalid
+    /// sequences, which the lexer reports, are kept as they are.
     pub fn unescape(s: &'a str) -> Cow<'a, str> {
-        Cow::Borrowed(s.trim_matches('"'))
+        let s = s.strip_prefix(['"', '}']).unwrap_or(s);
+        let s = s
+            .strip_suffix("${")
+            .or_else(|| s.strip_suffix('"'))
+            .unwrap_or(s);
+        if !s.contains('\\') {
+            return Cow::Borrowed(s);
+        }
+
+        let mut unescaped = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(backslash) = rest.find('\\') {
+            unescaped.push_str(&rest[..backslash]);
+            rest = &rest[backslash + 1..];
+            match decode_escape(rest) {
+                Some((c, len)) => {
+                    unescaped.push(c);
+                    rest = &rest[len..];
+                }
+                None => unescaped.push('\\'),
+            }
+        }
+        unescaped.push_str(rest);
+        Cow::Owned(unescaped)
     }
 }
+
+/// Decodes the escape sequence at the start of `escape`, the text right after
+/// a backslash, returning the character and the length of the sequence in bytes.
+pub(crate) fn decode_escape(escape: &str) -> Option<(char, usize)> {
+    let c = match escape.chars().next()? {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '\\' => '\\',
+        '"' => '"',
+        '$' => '$',
+        '0' => '\0',
+        'u' => {
+            let digits = escape.strip_prefix("u{")?;
+            let digits = &digits[..digits.find('}')?];
+            if !(1..=6).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let c = char::from_u32(u32::from_str_radix(digits, 16).ok()?)?;
+            // `u{` + digits + `}`
+            return Some((c, digits.len() + 3));
+        }
+        _ => return None,
+    };
+    Some((c, 1))
+}
@@ -1,20 +1,52 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use crate::error::{Error, ParseError, ParseErrorKind};
+use serde::{Serialize, ser::SerializeStruct};
 
-use super::{BinaryOperator, Keyword, Operator, UnaryOperator};
+use crate::error::ParseErrorKind;
+
+use super::{BinaryOperator, Keyword, Operator, Span, UnaryOperator};
 
 #[derive(Debug, Clone)]
 pub enum TokenTree<'a> {
-    Atom(Atom<'a>),
-    Cons(Op, Vec<TokenTree<'a>>),
+    /// An atom along with the span of its token.
+    Atom(Atom<'a>, Span),
+    /// An operation applied to its operands along with the span of the whole
+    /// expression, operands included.
+    Cons(Op, Vec<TokenTree<'a>>, Span),
+}
+
+impl TokenTree<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            TokenTree::Atom(_, span) | TokenTree::Cons(_, _, span) => *span,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.span().line()
+    }
+
+    /// Copies every borrowed string, so the tree no longer refers to the source.
+    pub fn into_owned(self) -> TokenTree<'static> {
+        match self {
+            TokenTree::Atom(atom, span) => TokenTree::Atom(atom.into_owned(), span),
+            TokenTree::Cons(op, operands, span) => TokenTree::Cons(
+                op,
+                operands.into_iter().map(TokenTree::into_owned).collect(),
+                span,
+            ),
+        }
+    }
 }
 
 impl std::fmt::Display for TokenTree<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TokenTree::Atom(atom) => write!(f, "{}", atom),
-            TokenTree::Cons(op, children) => {
+            TokenTree::Atom(atom, _) => write!(f, "{}", atom),
+            TokenTree::Cons(op, children, _) => {
                 write!(f, "({}", op)?;
                 for s in children {
                     write!(f, " {}", s)?
@@ -25,15 +57,80 @@ impl std::fmt::Display for TokenTree<'_> {
     }
 }
 
+/// Serializes atoms as `{"atom", "value", "span"}` objects and operations as
+/// `{"op", "operands", "span"}` objects, with the operands nested.
+impl Serialize for TokenTree<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tree = serializer.serialize_struct("TokenTree", 3)?;
+        match self {
+            TokenTree::Atom(atom, _) => {
+                let kind = match atom {
+                    Atom::String(_) => "string",
+                    Atom::Number(_) => "number",
+                    Atom::Nil => "nil",
+                    Atom::Bool(_) => "bool",
+                    Atom::Ident(..) => "identifier",
+                    Atom::Super(_) => "super",
+                    Atom::This(_) => "this",
+                };
+                tree.serialize_field("atom", kind)?;
+                match atom {
+                    Atom::String(s) | Atom::Ident(s, _) => tree.serialize_field("value", s)?,
+                    Atom::Number(n) => tree.serialize_field("value", n)?,
+                    Atom::Bool(b) => tree.serialize_field("value", b)?,
+                    Atom::Nil | Atom::Super(_) | Atom::This(_) => {
+                        tree.serialize_field("value", &())?
+                    }
+                }
+            }
+            TokenTree::Cons(op, operands, _) => {
+                tree.serialize_field("op", &op.to_string())?;
+                tree.serialize_field("operands", operands)?;
+            }
+        }
+        tree.serialize_field("span", &self.span())?;
+        tree.end()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Atom<'a> {
     String(Cow<'a, str>),
     Number(f64),
     Nil,
     Bool(bool),
-    Ident(&'a str),
-    Super,
-    This,
+    Ident(Cow<'a, str>, NodeId),
+    Super(NodeId),
+    This(NodeId),
+}
+
+impl Atom<'_> {
+    pub fn into_owned(self) -> Atom<'static> {
+        match self {
+            Atom::String(s) => Atom::String(Cow::Owned(s.into_owned())),
+            Atom::Number(n) => Atom::Number(n),
+            Atom::Nil => Atom::Nil,
+            Atom::Bool(b) => Atom::Bool(b),
+            Atom::Ident(name, id) => Atom::Ident(Cow::Owned(name.into_owned()), id),
+            Atom::Super(id) => Atom::Super(id),
+            Atom::This(id) => Atom::This(id),
+        }
+    }
+}
+
+/// Identifies an atom that may refer to a variable, so that the
+/// [`Resolver`](crate::Resolver) can record where it is declared.
+///
+/// Every ID is unique within the process, even across separately parsed
+/// programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn fresh() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 impl std::fmt::Display for Atom<'_> {
@@ -49,9 +146,9 @@ impl std::fmt::Display for Atom<'_> {
             }
             Atom::Nil => write!(f, "nil"),
             Atom::Bool(b) => write!(f, "{}", b),
-            Atom::Ident(i) => write!(f, "{}", i),
-            Atom::Super => write!(f, "super"),
-            Atom::This => write!(f, "this"),
+            Atom::Ident(i, _) => write!(f, "{}", i),
+            Atom::Super(_) => write!(f, "super"),
+            Atom::This(_) => write!(f, "this"),
         }
     }
 }
@@ -78,6 +175,9 @@ pub enum Op {
     Star,
     Slash,
 
+    Ternary,
+    Interpolate,
+
     Bang,
     BangEqual,
     Less,
@@ -107,6 +207,8 @@ impl std::fmt::Display for Op {
             Op::Minus => write!(f, "-"),
             Op::Star => write!(f, "*"),
             Op::Slash => write!(f, "/"),
+            Op::Ternary => write!(f, "?:"),
+            Op::Interpolate => write!(f, "interpolate"),
             Op::Bang => write!(f, "!"),
             Op::BangEqual => write!(f, "!="),
             Op::Less => write!(f, "<"),
@@ -123,15 +225,16 @@ impl Op {
     pub fn prefix_binding_power(&self) -> Option<((), u8)> {
         match self {
             Op::Print | Op::Return => Some(((), 1)),
-            Op::Bang | Op::Plus | Op::Minus => Some(((), 11)),
+            Op::Bang | Op::Plus | Op::Minus => Some(((), 15)),
             _ => None,
         }
     }
 
     pub fn postfix_binding_power(&self) -> Option<(u8, ())> {
         let res = match self {
-            Op::Bang => (11, ()),
-            // '[' => (11, ()),
+            Op::Bang => (15, ()),
+            Op::Call => (18, ()),
+            // '[' => (15, ()),
             _ => return None,
         };
         Some(res)
@@ -140,16 +243,18 @@ impl Op {
     pub fn infix_binding_power(&self) -> Option<(u8, u8)> {
         let res = match self {
             Op::Equal => (2, 1),
-            // '?' => (4, 3),
+            Op::Ternary => (4, 3),
+            Op::Or => (5, 6),
+            Op::And => (7, 8),
             Op::BangEqual
             | Op::EqualEqual
             | Op::Less
             | Op::LessEqual
             | Op::Greater
-            | Op::GreaterEqual => (5, 6),
-            Op::Plus | Op::Minus => (7, 8),
-            Op::Star | Op::Slash => (9, 10),
-            Op::Dot => (14, 13),
+            | Op::GreaterEqual => (9, 10),
+            Op::Plus | Op::Minus => (11, 12),
+            Op::Star | Op::Slash => (13, 14),
+            Op::Dot => (18, 17),
             _ => return None,
         };
         Some(res)
@@ -157,7 +262,7 @@ impl Op {
 }
 
 impl TryFrom<UnaryOperator> for Op {
-    type Error = Error;
+    type Error = ParseErrorKind;
 
     fn try_from(value: UnaryOperator) -> Result<Self, Self::Error> {
         match value {
@@ -167,29 +272,28 @@ impl TryFrom<UnaryOperator> for Op {
             UnaryOperator::Star => Ok(Op::Star),
             UnaryOperator::Slash => Ok(Op::Slash),
             UnaryOperator::Bang => Ok(Op::Bang),
-            op => Err(Error::ParseError(ParseError::new(
-                ParseErrorKind::UnsupportedOperator(Operator::Unary(op)),
-            ))),
+            UnaryOperator::Question => Ok(Op::Ternary),
+            op => Err(ParseErrorKind::UnsupportedOperator(Operator::Unary(op))),
         }
     }
 }
 
 impl TryFrom<Keyword> for Op {
-    type Error = Error;
+    type Error = ParseErrorKind;
 
     fn try_from(value: Keyword) -> Result<Self, Self::Error> {
         match value {
+            Keyword::And => Ok(Op::And),
+            Keyword::Or => Ok(Op::Or),
             Keyword::Print => Ok(Op::Print),
             Keyword::Return => Ok(Op::Return),
-            _ => Err(Error::ParseError(ParseError::new(
-                ParseErrorKind::UnsupportedKeyword(value),
-            ))),
+            _ => Err(ParseErrorKind::UnsupportedKeyword(value)),
         }
     }
 }
 
 impl TryFrom<BinaryOperator> for Op {
-    type Error = Error;
+    type Error = ParseErrorKind;
 
     fn try_from(value: BinaryOperator) -> Result<Self, Self::Error> {
         match value {
@@ -205,7 +309,7 @@ impl TryFrom<BinaryOperator> for Op {
 }
 
 impl TryFrom<Operator> for Op {
-    type Error = Error;
+    type Error = ParseErrorKind;
 
     fn try_from(value: Operator) -> Result<Self, Self::Error> {
         match value {